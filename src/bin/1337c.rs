@@ -1,43 +1,63 @@
-use std::fs::{self};
+use std::env;
 
 use the_1337_lang::*;
 
 fn main() {
-    let source_code = fs::read_to_string("example/first.1337").expect("failed to read source file");
-
-    println!("Tokenizing....");
-
-    println!("--------------------------------");
-    let mut lexer = Lexer::new(source_code);
-    let mut tokens: Vec<TokenInfo> = vec![];
-    for i in 0.. {
-        let Some(token) = lexer.tokenize() else {
-            break;
-        };
-        println!("{}: {:?}", i, token);
-        tokens.push(token);
+    let mut paths = env::args().skip(1).collect::<Vec<String>>();
+    if paths.is_empty() {
+        paths.push("example/first.1337".to_owned());
     }
-    println!("--------------------------------");
 
-    println!();
-
-    println!("Parsing...");
-    println!("================================");
+    let mut sources = SourceMap::new();
+    for path in paths {
+        sources.load(path).expect("failed to read source file");
+    }
 
-    let mut parser = Parser::new(tokens);
     let mut nodes = vec![];
-    while let Some(node) = parser.parse() {
-        println!("{:#?}", node);
-        nodes.push(node);
-        // if let Node::Invalid = node.node {
-        //     let parsed = &parser.tokens()[node.start_index..node.end_index];
-        //     println!("Bad parse: {:?}", parsed);
-        // }
-    }
+    for file in sources.files() {
+        println!("Tokenizing {}....", sources.name(file));
 
-    println!("================================");
+        println!("--------------------------------");
+        let mut lexer = Lexer::new(&sources, file);
+        let tokens = lexer.tokenize_all();
+        for (i, token) in tokens.iter().enumerate() {
+            println!("{}: {:?}", i, token);
+        }
+        for error in lexer.errors() {
+            println!(
+                "error at '{}': {:?}",
+                sources.location(&error.start_pos),
+                error.kind
+            );
+        }
+        for warning in lexer.warnings() {
+            println!(
+                "warning at '{}': {}",
+                sources.location(&warning.start_pos),
+                warning.message
+            );
+        }
+        println!("--------------------------------");
 
-    println!();
+        println!();
+
+        println!("Parsing {}...", sources.name(file));
+        println!("================================");
+
+        let mut parser = Parser::new(tokens).expect("the lexer always emits EOF");
+        while let Some(node) = parser.parse() {
+            println!("{:#?}", node);
+            nodes.push(node);
+            // if let Node::Invalid = node.node {
+            //     let parsed = &parser.tokens()[node.start_index..node.end_index];
+            //     println!("Bad parse: {:?}", parsed);
+            // }
+        }
+
+        println!("================================");
+
+        println!();
+    }
 
     println!("Analyzing...");
     println!("********************************");
@@ -1,27 +1,372 @@
-use crate::{KEYWORDS, NUMBER_FORMATS, NumberFormat, Position, SYMBOLS, TokenInfo, token::Token};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
-pub struct Lexer {
-    content: String,
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+use unicode_security::{MixedScript, skeleton};
+
+use crate::{
+    FileId, KEYWORDS, MAX_SYMBOL_LEN, NUMBER_FORMATS, NUMBER_SUFFIXES, NumberFormat, Position,
+    STRING_PREFIXES, SYMBOLS, SourceMap, StringPrefix, TokenInfo, Trivia, TriviaKind, token::Token,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    // Line of a long string not indented like its closing line
+    InsufficientIndentation,
+    // Even-lengthed quote runs, which are ambiguous
+    AmbiguousQuoteRun,
+    UnterminatedComment,
+    UnterminatedCharLiteral,
+    EmptyCharLiteral,
+    // More than one character between single quotes
+    OverlongCharLiteral,
+    InvalidEscape(String),
+    // C and UTF-16 strings are null terminated, so they can't contain nulls
+    NulInTerminatedString,
+    // Binary strings only contain hex digits and whitespace
+    InvalidBinaryDigit(char),
+    // Binary string with an odd number of hex digits
+    IncompleteByte,
+    // Digit not allowed by the number format, e.g. `0b2`
+    InvalidDigit(char),
+    MissingDigits,
+    UnexpectedDecimalPoint,
+    MissingExponent,
+    InvalidSuffix(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub start_pos: Position,
+    pub end_pos: Position,
+}
+
+#[derive(Debug, Clone)]
+pub struct LexWarning {
+    pub message: String,
+    pub start_pos: Position,
+    pub end_pos: Position,
+}
+
+/// Replacement of a byte range of the source, as sent by an editor
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+// Byte offset of the end of a token, including its trailing trivia
+fn full_end(token: &TokenInfo) -> usize {
+    token.end_pos.index
+        + token
+            .trailing_trivia
+            .iter()
+            .map(|x| x.text.len())
+            .sum::<usize>()
+}
+
+// Range of the new source holding `bytes`, a slice of `old_content`
+// that was moved by `delta` bytes
+fn moved_range(old_content: &str, bytes: &[u8], delta: isize) -> Range<usize> {
+    let offset = bytes.as_ptr() as usize - old_content.as_ptr() as usize;
+    debug_assert!(offset + bytes.len() <= old_content.len());
+    let start = offset.wrapping_add_signed(delta);
+    start..start + bytes.len()
+}
+
+// Content of a literal, borrowed from the source
+// for as long as it matches the source verbatim
+struct LiteralContent<'src> {
+    source: &'src str,
+    start: usize,
+    end: usize,
+    owned: Option<Vec<u8>>,
+}
+
+impl<'src> LiteralContent<'src> {
+    fn new(source: &'src str, start: usize) -> Self {
+        Self {
+            source,
+            start,
+            end: start,
+            owned: None,
+        }
+    }
+
+    /// Appends the source character at `index`
+    fn push_source(&mut self, index: usize, c: char) {
+        if self.owned.is_none() && index == self.end {
+            self.end += c.len_utf8();
+        } else {
+            self.push_bytes(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let (source, start, end) = (self.source, self.start, self.end);
+        self.owned
+            .get_or_insert_with(|| source.as_bytes()[start..end].to_vec())
+            .extend_from_slice(bytes);
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match &self.owned {
+            Some(owned) => owned,
+            None => &self.source.as_bytes()[self.start..self.end],
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        match &mut self.owned {
+            Some(owned) => owned.truncate(len),
+            None => self.end = self.start + len,
+        }
+    }
+
+    fn into_bytes(self) -> Cow<'src, [u8]> {
+        match self.owned {
+            Some(owned) => Cow::Owned(owned),
+            None => Cow::Borrowed(&self.source.as_bytes()[self.start..self.end]),
+        }
+    }
+
+    fn into_str(self) -> Cow<'src, str> {
+        match self.owned {
+            Some(owned) => {
+                Cow::Owned(String::from_utf8(owned).expect("String content must be valid UTF-8"))
+            }
+            None => Cow::Borrowed(&self.source[self.start..self.end]),
+        }
+    }
+}
+
+pub struct Lexer<'src> {
+    file: FileId,
+    content: &'src str,
+    // Byte offset of the cursor into `content`
     index: usize,
     line: usize,
     column: usize,
+    errors: Vec<LexError>,
+    warnings: Vec<LexWarning>,
+    // Confusable skeletons of the identifiers seen so far,
+    // mapped to the first identifier that produced them
+    skeletons: HashMap<String, String>,
+    // Identifier spellings already checked for confusables,
+    // so that repeated identifiers are only checked once
+    checked_identifiers: HashSet<String>,
+    emitted_eof: bool,
+    // Attach whitespace and comments to tokens as trivia,
+    // instead of skipping them or emitting comment tokens
+    keep_trivia: bool,
 }
 
 // TODO: Consider not allowing private tokenize functions
 //       to return an optional. Instead, they may either
-//       return the expected token or Token::Unknown
-impl Lexer {
-    pub fn new(content: String) -> Self {
+//       return the expected token or Token::Invalid
+impl<'src> Lexer<'src> {
+    /// Create a lexer for `file`, whose content is taken from `sources`
+    pub fn new(sources: &'src SourceMap, file: FileId) -> Self {
         Self {
-            content,
+            file,
+            content: sources.content(file),
             index: 0,
             line: 1,
             column: 1,
+            errors: vec![],
+            warnings: vec![],
+            skeletons: HashMap::new(),
+            checked_identifiers: HashSet::new(),
+            emitted_eof: false,
+            keep_trivia: false,
+        }
+    }
+
+    /// Create a lexer whose tokens carry their surrounding trivia,
+    /// so that the token stream reproduces the source exactly
+    pub fn with_trivia(sources: &'src SourceMap, file: FileId) -> Self {
+        Self {
+            keep_trivia: true,
+            ..Self::new(sources, file)
+        }
+    }
+
+    /// Tokenize the remaining content, up to and including EOF
+    pub fn tokenize_all(&mut self) -> Vec<TokenInfo<'src>> {
+        self.by_ref().collect()
+    }
+
+    /// Update `tokens`, lexed from `old_content` before `edit`, to match the
+    /// content of this lexer, which must not have tokenized anything yet.
+    ///
+    /// Tokens are relexed from the last one that ends safely before the edit, until
+    /// a new token starts where an old one after the edit did. The remaining
+    /// old tokens are reused with shifted positions. Only relexed tokens
+    /// report errors and warnings.
+    pub fn relex(
+        &mut self,
+        old_content: &str,
+        mut tokens: Vec<TokenInfo<'src>>,
+        edit: &TextEdit,
+    ) -> Vec<TokenInfo<'src>> {
+        let delta = edit.replacement.len() as isize - edit.range.len() as isize;
+        debug_assert_eq!(
+            old_content.len().checked_add_signed(delta),
+            Some(self.content.len()),
+            "edit doesn't match the new content",
+        );
+
+        // The lexer looks at most two characters past the end of a token
+        // (e.g. `1.5`), so tokens that end at least that far from the edit
+        // can't change. Tokens spanning the edit, like long strings and
+        // block comments, are relexed from their start. Trailing trivia stops
+        // before an unterminated block comment, which the edit may close,
+        // so tokens followed by an invalid token are relexed too.
+        let kept = tokens
+            .windows(2)
+            .rposition(|pair| {
+                pair[1].token != Token::Invalid
+                    && self
+                        .content
+                        .get(full_end(&pair[0])..edit.range.start)
+                        .is_some_and(|gap| gap.chars().nth(1).is_some())
+            })
+            .map_or(0, |x| x + 1);
+        let mut old_tokens = tokens.split_off(kept).into_iter().peekable();
+        for token in &mut tokens {
+            token.start_pos.file = self.file;
+            token.end_pos.file = self.file;
+            self.move_borrowed_text(old_content, token, 0);
+        }
+        if let Some(last) = tokens.last() {
+            self.index = last.end_pos.index;
+            self.line = last.end_pos.line;
+            self.column = last.end_pos.column;
+            while self.index < full_end(last) {
+                self.skip();
+            }
+        }
+
+        while let Some(token) = self.tokenize() {
+            // Apart from the shebang and banner comments, which only exist
+            // at the start of the file, lexing only depends on what follows.
+            // So once a token starts where an old one after the edit did,
+            // and neither is at the start, the rest are the same.
+            let start = token.start_pos.index;
+            while let Some(old) = old_tokens.peek()
+                && (old.start_pos.index < edit.range.end
+                    || old.start_pos.index.saturating_add_signed(delta) < start)
+            {
+                old_tokens.next();
+            }
+
+            let synced = old_tokens.next_if(|x| {
+                start != 0
+                    && x.start_pos.index != 0
+                    && x.start_pos.index.saturating_add_signed(delta) == start
+            });
+            let (line, column) = (token.start_pos.line, token.start_pos.column);
+            tokens.push(token);
+
+            let Some(synced) = synced else {
+                continue;
+            };
+
+            let sync_line = synced.start_pos.line;
+            let line_delta = line as isize - sync_line as isize;
+            let column_delta = column as isize - synced.start_pos.column as isize;
+            let shift = |pos: &mut Position| {
+                pos.file = self.file;
+                pos.index = pos.index.wrapping_add_signed(delta);
+                if pos.line == sync_line {
+                    pos.column = pos.column.wrapping_add_signed(column_delta);
+                }
+                pos.line = pos.line.wrapping_add_signed(line_delta);
+            };
+
+            tokens.extend(old_tokens.map(|mut x| {
+                shift(&mut x.start_pos);
+                shift(&mut x.end_pos);
+                self.move_borrowed_text(old_content, &mut x, delta);
+                x
+            }));
+            break;
+        }
+
+        // Leave the lexer at the end, as if it had tokenized everything
+        if let Some(eof) = tokens.last() {
+            self.index = eof.end_pos.index;
+            self.line = eof.end_pos.line;
+            self.column = eof.end_pos.column;
+            self.emitted_eof = true;
+        }
+
+        tokens
+    }
+
+    // Borrowed text of a reused token still points into the old source,
+    // so borrow it again from the same text, `delta` bytes further
+    fn move_borrowed_text(&self, old_content: &str, token: &mut TokenInfo<'src>, delta: isize) {
+        let content = self.content;
+        let move_str = |text: &mut &'src str| {
+            *text = &content[moved_range(old_content, text.as_bytes(), delta)];
+        };
+
+        match &mut token.token {
+            Token::Comment(text)
+            | Token::Shebang(text)
+            | Token::Identifier(Cow::Borrowed(text))
+            | Token::String(Cow::Borrowed(text))
+            | Token::CString(Cow::Borrowed(text))
+            | Token::WideString(Cow::Borrowed(text)) => move_str(text),
+            Token::ByteString(Cow::Borrowed(bytes)) => {
+                *bytes = &content.as_bytes()[moved_range(old_content, bytes, delta)];
+            }
+            Token::Number { value, suffix, .. } => {
+                if let Cow::Borrowed(value) = value {
+                    move_str(value);
+                }
+                if let Some(suffix) = suffix {
+                    move_str(suffix);
+                }
+            }
+            _ => {}
         }
+
+        for trivia in token
+            .leading_trivia
+            .iter_mut()
+            .chain(token.trailing_trivia.iter_mut())
+        {
+            move_str(&mut trivia.text);
+        }
+    }
+
+    pub fn errors(&self) -> &Vec<LexError> {
+        &self.errors
+    }
+
+    pub fn warnings(&self) -> &Vec<LexWarning> {
+        &self.warnings
+    }
+
+    fn error(&mut self, kind: LexErrorKind, start_pos: Position, end_pos: Position) {
+        self.errors.push(LexError {
+            kind,
+            start_pos,
+            end_pos,
+        });
     }
 
     fn peek(&self, index: usize) -> Option<char> {
-        self.content.chars().nth(index)
+        self.content.get(index..)?.chars().next()
     }
 
     fn current(&self) -> Option<char> {
@@ -29,17 +374,18 @@ impl Lexer {
     }
 
     fn skip(&mut self) {
-        let current = self.current();
-        if let Some(c) = current {
-            if c == '\n' {
-                self.line += 1;
-                self.column = 1;
-            } else if c != '\r' {
-                self.column += 1;
-            }
+        let Some(c) = self.current() else {
+            return;
+        };
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if c != '\r' {
+            self.column += 1;
         }
 
-        self.index += 1;
+        self.index += c.len_utf8();
     }
 
     fn advance(&mut self) -> Option<char> {
@@ -59,34 +405,150 @@ impl Lexer {
         }
     }
 
-    fn position(&self) -> Position {
+    /// Leading trivia spans multiple lines, while trailing
+    /// trivia stops right before the end of the token's line
+    fn tokenize_trivia(&mut self, is_leading: bool) -> Vec<Trivia<'src>> {
+        let mut trivia = vec![];
+        while let Some(c) = self.current() {
+            let start = self.index;
+            let is_newline = c == '\n' || self.content[self.index..].starts_with("\r\n");
+            let kind = match c {
+                _ if is_newline && !is_leading => break,
+                _ if is_newline => {
+                    if c == '\r' {
+                        self.skip();
+                    }
+                    self.skip();
+                    TriviaKind::Newline
+                }
+                // The shebang is a token of its own, not a comment
+                '#' if self.is_shebang() => break,
+                '#' => {
+                    // Unterminated block comments are invalid tokens,
+                    // as they are without trivia, so they are lexed again
+                    let (index, line, column) = (self.index, self.line, self.column);
+                    let error_count = self.errors.len();
+                    if self.tokenize_comment() == Some(Token::Invalid) {
+                        (self.index, self.line, self.column) = (index, line, column);
+                        self.errors.truncate(error_count);
+                        break;
+                    }
+                    TriviaKind::Comment
+                }
+                _ if c.is_whitespace() => {
+                    while let Some(c) = self.current() {
+                        if !c.is_whitespace()
+                            || c == '\n'
+                            || self.content[self.index..].starts_with("\r\n")
+                        {
+                            break;
+                        }
+
+                        self.skip();
+                    }
+                    TriviaKind::Whitespace
+                }
+                _ => break,
+            };
+
+            trivia.push(Trivia {
+                kind,
+                text: &self.content[start..self.index],
+            });
+        }
+
+        trivia
+    }
+
+    fn current_position(&self) -> Position {
         Position {
+            file: self.file,
             index: self.index,
             line: self.line,
             column: self.column,
         }
     }
 
-    fn tokenize_identifier_or_keyword(&mut self) -> Option<Token> {
-        let mut ident = String::new();
+    fn tokenize_identifier_or_keyword(&mut self) -> Option<Token<'src>> {
+        let start_pos = self.current_position();
         while let Some(c) = self.current() {
-            match c {
-                'a'..'z' | 'A'..'Z' | '0'..'9' | '_' => {
-                    ident.push(c);
-                    self.skip();
-                    continue;
-                }
-                _ => break,
-            };
+            if c != '_' && !is_xid_continue(c) {
+                break;
+            }
+
+            self.skip();
+        }
+
+        // Equivalent spellings must resolve to the same identifier
+        let ident = &self.content[start_pos.index..self.index];
+        let ident: Cow<'src, str> = if is_nfc(ident) {
+            Cow::Borrowed(ident)
+        } else {
+            Cow::Owned(ident.nfc().collect())
+        };
+
+        // Prefixed string literal, e.g. c"..."
+        if self.current() == Some('"')
+            && let Some(prefix) = STRING_PREFIXES.get(&ident)
+        {
+            return self.tokenize_string(prefix.to_owned(), start_pos);
+        }
+
+        if let Some(keyword) = KEYWORDS.get(&ident) {
+            return Some(keyword.to_owned());
+        }
+
+        self.check_identifier(&ident, start_pos);
+
+        Some(Token::Identifier(ident))
+    }
+
+    /// Warns about the first occurrence of a suspicious identifier
+    fn check_identifier(&mut self, ident: &str, start_pos: Position) {
+        if self.checked_identifiers.contains(ident) {
+            return;
+        }
+        self.checked_identifiers.insert(ident.to_owned());
+
+        let mut messages = vec![];
+        if !ident.is_single_script() {
+            messages.push(format!("identifier '{}' mixes multiple scripts", ident));
+        }
+
+        let ident_skeleton = skeleton(ident).collect::<String>();
+        match self.skeletons.get(&ident_skeleton) {
+            // Confusables made up of ASCII only are most likely intended
+            Some(other) if other != ident && !(other.is_ascii() && ident.is_ascii()) => {
+                messages.push(format!(
+                    "identifier '{}' is confusable with '{}'",
+                    ident, other
+                ));
+            }
+            Some(_) => {}
+            None => {
+                self.skeletons.insert(ident_skeleton, ident.to_owned());
+            }
         }
 
-        KEYWORDS
-            .get(ident.as_str())
-            .map(|token| token.to_owned())
-            .or_else(|| Some(Token::Identifier(ident)))
+        for message in messages {
+            self.warnings.push(LexWarning {
+                message,
+                start_pos: start_pos.clone(),
+                end_pos: self.current_position(),
+            });
+        }
     }
 
-    fn tokenize_string(&mut self) -> Option<Token> {
+    /// `start_pos` is the start of the string prefix, if any
+    fn tokenize_string(
+        &mut self,
+        prefix: StringPrefix,
+        start_pos: Position,
+    ) -> Option<Token<'src>> {
+        let is_byte = prefix == StringPrefix::Byte;
+        let is_terminated = matches!(prefix, StringPrefix::C | StringPrefix::Utf16);
+        // Binary strings have no escapes, only hex digits
+        let is_binary = prefix == StringPrefix::Binary;
         let mut quote_count: usize = 0;
         while let Some(c) = self.current() {
             if c != '"' {
@@ -98,121 +560,757 @@ impl Lexer {
         }
 
         if quote_count == 2 {
-            return Some(Token::String("".to_owned()));
+            let content = LiteralContent::new(self.content, self.index);
+            return Some(Self::string_token(prefix, content));
         } else if quote_count & 1 == 0 {
             // Even-lengthed long quotes not allowed!
             // They are ambiguous.
-            return Some(Token::Unknown('"'.to_string().repeat(quote_count)));
+            self.error(
+                LexErrorKind::AmbiguousQuoteRun,
+                start_pos,
+                self.current_position(),
+            );
+            return Some(Token::Invalid);
+        }
+
+        // Long strings are dedented by the indentation of
+        // their closing line, and their first newline is dropped
+        let is_long = quote_count > 1;
+        let (indent, is_closing_line_blank) = if is_long {
+            self.long_string_indent(quote_count)
+        } else {
+            (String::new(), false)
+        };
+        if is_long && self.content[self.index..].starts_with("\r\n") {
+            self.skip();
+        }
+        let mut is_line_start = is_long && self.current() == Some('\n');
+        if is_line_start {
+            self.skip();
         }
 
         let mut end_quote_count = 0;
-        let mut string_content = String::new();
+        let mut string_content = LiteralContent::new(self.content, self.index);
+        let mut is_valid = true;
+        // Span of the last hex digit of a binary string, and whether
+        // it is the first digit of a byte
+        let mut last_digit: Option<(Position, Position)> = None;
+        let mut is_odd_digit_count = false;
+        let mut quote_run_start = None;
         while end_quote_count != quote_count {
+            if is_line_start {
+                is_line_start = false;
+                is_valid &= self.skip_indentation(&indent);
+            }
+
+            let char_start = self.current_position();
             let Some(c) = self.advance() else {
-                return Some(Token::Unknown(format!(
-                    "{}{}",
-                    '"'.to_string().repeat(quote_count),
-                    string_content
-                )));
+                self.error(
+                    LexErrorKind::UnterminatedString,
+                    start_pos,
+                    self.current_position(),
+                );
+                return Some(Token::Invalid);
             };
 
-            string_content.push(c);
+            // Quotes that don't close a binary string aren't hex digits
+            if is_binary && c != '"' {
+                if let Some(quote_start) = quote_run_start.take() {
+                    is_valid = false;
+                    self.error(
+                        LexErrorKind::InvalidBinaryDigit('"'),
+                        quote_start,
+                        char_start.clone(),
+                    );
+                }
+
+                if c.is_ascii_hexdigit() {
+                    last_digit = Some((char_start.clone(), self.current_position()));
+                    is_odd_digit_count = !is_odd_digit_count;
+                } else if !c.is_whitespace() {
+                    is_valid = false;
+                    self.error(
+                        LexErrorKind::InvalidBinaryDigit(c),
+                        char_start.clone(),
+                        self.current_position(),
+                    );
+                }
+            }
+
+            if c == '\\' && !is_binary {
+                end_quote_count = 0;
+                match self.tokenize_escape(is_byte) {
+                    Ok('\0') if is_terminated => {
+                        is_valid = false;
+                        self.error(
+                            LexErrorKind::NulInTerminatedString,
+                            char_start,
+                            self.current_position(),
+                        );
+                    }
+                    // Escapes in byte strings are always single bytes
+                    Ok(escaped) if is_byte => string_content.push_bytes(&[escaped as u8]),
+                    Ok(escaped) => {
+                        string_content.push_bytes(escaped.encode_utf8(&mut [0; 4]).as_bytes())
+                    }
+                    Err(escape) => {
+                        // Keep going until the end of the string,
+                        // so that lexing resumes after it
+                        is_valid = false;
+                        self.error(
+                            LexErrorKind::InvalidEscape(format!("\\{}", escape)),
+                            char_start,
+                            self.current_position(),
+                        );
+                    }
+                }
+                continue;
+            }
+
+            if c == '\0' && is_terminated {
+                is_valid = false;
+                self.error(
+                    LexErrorKind::NulInTerminatedString,
+                    char_start.clone(),
+                    self.current_position(),
+                );
+            }
+
+            string_content.push_source(char_start.index, c);
 
             if c == '"' {
+                if end_quote_count == 0 {
+                    quote_run_start = Some(char_start);
+                }
                 end_quote_count += 1;
             } else {
                 end_quote_count = 0;
             }
+
+            is_line_start = is_long && c == '\n';
+        }
+
+        if is_valid
+            && is_odd_digit_count
+            && let Some((digit_start, digit_end)) = last_digit
+        {
+            is_valid = false;
+            self.error(LexErrorKind::IncompleteByte, digit_start, digit_end);
+        }
+
+        if !is_valid {
+            return Some(Token::Invalid);
         }
 
         // Remove trailing double quotes from string content
-        string_content.truncate(string_content.len() - end_quote_count);
+        string_content.truncate(string_content.as_bytes().len() - end_quote_count);
 
-        Some(Token::String(string_content))
+        // The newline before a blank closing line is not part of the string
+        if is_closing_line_blank && string_content.as_bytes().ends_with(b"\n") {
+            string_content.truncate(string_content.as_bytes().len() - 1);
+            if string_content.as_bytes().ends_with(b"\r") {
+                string_content.truncate(string_content.as_bytes().len() - 1);
+            }
+        }
+
+        Some(Self::string_token(prefix, string_content))
     }
 
-    fn tokenize_number(&mut self) -> Option<Token> {
-        let mut value = String::new();
-        let mut format = NumberFormat::Decimal;
-        let mut is_float = false;
-        while let Some(c) = self.current() {
+    /// Looks ahead for the closing quotes of a long string and returns
+    /// the indentation of their line, and whether that line is blank
+    fn long_string_indent(&self, quote_count: usize) -> (String, bool) {
+        let rest = &self.content[self.index..];
+        let mut chars = rest.char_indices();
+        let mut end_quote_count = 0;
+        while let Some((i, c)) = chars.next() {
             match c {
-                'x' | 'X' | 'b' | 'B' | 'o' | 'O' if value == "0" => {
-                    format = NUMBER_FORMATS
-                        .get(&c.to_ascii_lowercase())
-                        .expect(format!("Missing expected number format: {}", c).as_str())
-                        .to_owned();
+                '\\' => {
+                    chars.next();
+                    end_quote_count = 0;
+                }
+                '"' => end_quote_count += 1,
+                _ => end_quote_count = 0,
+            }
+
+            if end_quote_count != quote_count {
+                continue;
+            }
+
+            let closing_start = i + 1 - quote_count;
+            let Some(line_start) = rest[..closing_start].rfind('\n').map(|x| x + 1) else {
+                // Closed in the same line it was opened
+                return (String::new(), false);
+            };
+
+            let line = &rest[line_start..closing_start];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            return (indent.to_owned(), indent.len() == line.len());
+        }
+
+        // Unterminated, which will be reported when lexing the string
+        (String::new(), false)
+    }
+
+    /// Skips the indentation at the start of a long string line.
+    /// Returns false if the line isn't properly indented.
+    fn skip_indentation(&mut self, indent: &str) -> bool {
+        if self.content[self.index..].starts_with(indent) {
+            for _ in indent.chars() {
+                self.skip();
+            }
+
+            return true;
+        }
+
+        // Blank lines don't need to be indented
+        let line_start = self.current_position();
+        while let Some(c) = self.current() {
+            if c == '\n' || !c.is_whitespace() {
+                break;
+            }
+
+            self.skip();
+        }
+
+        if matches!(self.current(), None | Some('\n')) {
+            return true;
+        }
+
+        self.error(
+            LexErrorKind::InsufficientIndentation,
+            line_start,
+            self.current_position(),
+        );
+        false
+    }
+
+    fn string_token(prefix: StringPrefix, content: LiteralContent<'src>) -> Token<'src> {
+        // Only byte strings may contain non UTF-8 data
+        match prefix {
+            StringPrefix::None => Token::String(content.into_str()),
+            StringPrefix::Byte => Token::ByteString(content.into_bytes()),
+            StringPrefix::C => Token::CString(content.into_str()),
+            StringPrefix::Utf16 => Token::WideString(content.into_str()),
+            StringPrefix::Binary => Token::BinaryString(Self::decode_binary_string(&content)),
+        }
+    }
+
+    // Content was validated while tokenizing the
+    // string, so it's only pairs of hex digits
+    fn decode_binary_string(content: &LiteralContent) -> Vec<u8> {
+        let digits = content
+            .as_bytes()
+            .iter()
+            .filter_map(|x| (*x as char).to_digit(16))
+            .collect::<Vec<u32>>();
+
+        digits
+            .chunks_exact(2)
+            .map(|x| (x[0] << 4 | x[1]) as u8)
+            .collect()
+    }
+
+    fn tokenize_char(&mut self) -> Option<Token<'src>> {
+        let start_pos = self.current_position();
+        self.skip(); // Skip opening quote
+
+        let escape_start = self.current_position();
+        let value = match self.current() {
+            None | Some('\n') => {
+                self.error(
+                    LexErrorKind::UnterminatedCharLiteral,
+                    start_pos,
+                    self.current_position(),
+                );
+                return Some(Token::Invalid);
+            }
+            Some('\'') => {
+                self.skip();
+                self.error(
+                    LexErrorKind::EmptyCharLiteral,
+                    start_pos,
+                    self.current_position(),
+                );
+                return Some(Token::Invalid);
+            }
+            Some('\\') => {
+                self.skip();
+                self.tokenize_escape(false)
+                    .map_err(|escape| LexErrorKind::InvalidEscape(format!("\\{}", escape)))
+            }
+            Some(c) => {
+                self.skip();
+                Ok(c)
+            }
+        };
+
+        if self.current() == Some('\'') {
+            self.skip();
+            return Some(match value {
+                Ok(c) => Token::Char(c),
+                Err(kind) => {
+                    self.error(kind, escape_start, self.current_position());
+                    Token::Invalid
+                }
+            });
+        }
+
+        // Look for the closing quote in the same line
+        // to tell overlong and unterminated literals apart
+        while let Some(c) = self.current() {
+            if c == '\n' {
+                break;
+            }
+
+            self.skip();
+            if c == '\'' {
+                self.error(
+                    LexErrorKind::OverlongCharLiteral,
+                    start_pos,
+                    self.current_position(),
+                );
+                return Some(Token::Invalid);
+            }
+        }
+
+        self.error(
+            LexErrorKind::UnterminatedCharLiteral,
+            start_pos,
+            self.current_position(),
+        );
+        Some(Token::Invalid)
+    }
+
+    /// Decodes an escape sequence, right after its backslash.
+    /// On failure, returns the text of the bad escape sequence.
+    fn tokenize_escape(&mut self, is_byte: bool) -> Result<char, String> {
+        let Some(c) = self.advance() else {
+            return Err("".to_owned());
+        };
+
+        match c {
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            '\\' => Ok('\\'),
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            'x' => {
+                let mut digits = String::new();
+                while digits.len() < 2 {
+                    match self.current() {
+                        Some(d) if d.is_ascii_hexdigit() => {
+                            digits.push(d);
+                            self.skip();
+                        }
+                        _ => break,
+                    }
+                }
+
+                // Only ASCII values are allowed in UTF-8 strings
+                u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|x| digits.len() == 2 && (is_byte || x.is_ascii()))
+                    .map(char::from)
+                    .ok_or(format!("x{}", digits))
+            }
+            'u' => {
+                if self.current() != Some('{') {
+                    return Err("u".to_owned());
+                }
+                self.skip();
+
+                let mut digits = String::new();
+                while let Some(d) = self.current() {
+                    if !d.is_ascii_hexdigit() {
+                        break;
+                    }
+
+                    digits.push(d);
                     self.skip();
-                    continue;
                 }
-                '.' if !is_float => {
-                    is_float = true;
+
+                if self.current() != Some('}') {
+                    return Err(format!("u{{{}", digits));
                 }
-                '0' | '1' => {}
-                '2'..='7' if format != NumberFormat::Binary => {}
-                '8'..='9' if format != NumberFormat::Binary && format != NumberFormat::Octal => {}
-                'a'..='f' | 'A'..='F' if format == NumberFormat::Hex => {}
-                _ => {
-                    // TODO: Handle invalid numbers
+                self.skip();
+
+                // Byte strings only hold single bytes
+                Some(digits.as_str())
+                    .filter(|x| !is_byte && (1..=6).contains(&x.len()))
+                    .and_then(|x| u32::from_str_radix(x, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or(format!("u{{{}}}", digits))
+            }
+            _ => Err(c.to_string()),
+        }
+    }
+
+    fn is_shebang(&self) -> bool {
+        self.index == 0 && self.content.starts_with("#!")
+    }
+
+    fn tokenize_shebang(&mut self) -> Option<Token<'src>> {
+        self.skip();
+        self.skip();
+
+        // Ends like a line comment, so a `\r` before the newline is kept
+        let command_start = self.index;
+        while let Some(c) = self.current() {
+            if c == '\n' {
+                break;
+            }
+
+            self.skip();
+        }
+
+        Some(Token::Shebang(&self.content[command_start..self.index]))
+    }
+
+    fn tokenize_comment(&mut self) -> Option<Token<'src>> {
+        let start_pos = self.current_position();
+        let mut hash_count: usize = 0;
+        while let Some('#') = self.current() {
+            hash_count += 1;
+            self.skip();
+        }
+
+        // A first line made only of hashes is a line comment, not a
+        // block comment opener, so that files can start with a banner
+        let rest = &self.content[self.index..];
+        if hash_count > 1
+            && start_pos.index == 0
+            && (rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n"))
+        {
+            return Some(Token::Comment(
+                &self.content[start_pos.index + 1..self.index],
+            ));
+        }
+
+        // Single hash comments last until the end of the line
+        let comment_start = self.index;
+        if hash_count == 1 {
+            while let Some(c) = self.current() {
+                if c == '\n' {
                     break;
                 }
+
+                self.skip();
+            }
+
+            return Some(Token::Comment(&self.content[comment_start..self.index]));
+        }
+
+        // Multiple hash comments last until a run of
+        // exactly the same amount of hashes is found
+        let mut end_hash_count = 0;
+        loop {
+            let c = self.current();
+            if c == Some('#') {
+                end_hash_count += 1;
+                self.skip();
+                continue;
+            }
+
+            if end_hash_count == hash_count {
+                break;
+            }
+            end_hash_count = 0;
+
+            if c.is_none() {
+                self.error(
+                    LexErrorKind::UnterminatedComment,
+                    start_pos,
+                    self.current_position(),
+                );
+                return Some(Token::Invalid);
+            }
+
+            self.skip();
+        }
+
+        // Exclude trailing hashes from comment content
+        Some(Token::Comment(
+            &self.content[comment_start..self.index - end_hash_count],
+        ))
+    }
+
+    fn tokenize_number(&mut self) -> Option<Token<'src>> {
+        let start_pos = self.current_position();
+
+        // Consume everything that could belong to the literal first,
+        // so that malformed literals don't split into extra tokens
+        while let Some(c) = self.current() {
+            let raw = &self.content[start_pos.index..self.index];
+            let is_hex = raw.starts_with("0x") || raw.starts_with("0X");
+            let accept = match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => true,
+                // Only a fractional part if followed by a digit,
+                // otherwise it may be e.g. a member access
+                '.' => self
+                    .peek(self.index + 1)
+                    .is_some_and(|x| x.is_ascii_digit() || (is_hex && x.is_ascii_hexdigit())),
+                // Exponent sign
+                '+' | '-' => match raw.chars().last() {
+                    Some('e' | 'E') => !is_hex,
+                    Some('p' | 'P') => is_hex,
+                    _ => false,
+                },
+                _ => false,
+            };
+
+            if !accept {
+                break;
             }
 
-            value.push(c);
             self.skip();
         }
 
-        Some(Token::Number {
+        match Self::parse_number(&self.content[start_pos.index..self.index]) {
+            Ok(token) => Some(token),
+            Err((kind, start, end)) => {
+                // The literal is ASCII only and can't span multiple lines
+                let offset_position = |offset: usize| Position {
+                    file: start_pos.file,
+                    index: start_pos.index + offset,
+                    line: start_pos.line,
+                    column: start_pos.column + offset,
+                };
+                self.error(kind, offset_position(start), offset_position(end));
+                Some(Token::Invalid)
+            }
+        }
+    }
+
+    /// Consumes digits of the given radix, ignoring separators,
+    /// and returns how many digits were found
+    fn parse_digits(
+        raw: &str,
+        index: &mut usize,
+        radix: u32,
+    ) -> Result<usize, (LexErrorKind, usize, usize)> {
+        let mut count = 0;
+        while let Some(c) = raw[*index..].chars().next() {
+            if c.is_digit(radix) {
+                count += 1;
+            } else if c.is_ascii_digit() {
+                // Decimal digit out of the radix range
+                return Err((LexErrorKind::InvalidDigit(c), *index, *index + 1));
+            } else if c != '_' {
+                break;
+            }
+
+            *index += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Validates a raw numeric literal. On failure, returns
+    /// the error and byte range of the offending part of the literal
+    fn parse_number(raw: &'src str) -> Result<Token<'src>, (LexErrorKind, usize, usize)> {
+        let mut chars = raw.chars();
+        let (format, mut index) = match (chars.next(), chars.next()) {
+            (Some('0'), Some(c)) if NUMBER_FORMATS.contains_key(&c.to_ascii_lowercase()) => {
+                (NUMBER_FORMATS[&c.to_ascii_lowercase()].to_owned(), 2)
+            }
+            _ => (NumberFormat::Decimal, 0),
+        };
+        let radix = match format {
+            NumberFormat::Decimal => 10,
+            NumberFormat::Hex => 16,
+            NumberFormat::Octal => 8,
+            NumberFormat::Binary => 2,
+        };
+        let exponent_marker = match format {
+            NumberFormat::Decimal => Some('e'),
+            NumberFormat::Hex => Some('p'),
+            _ => None,
+        };
+
+        let digits_start = index;
+        let mut is_float = false;
+
+        if Self::parse_digits(raw, &mut index, radix)? == 0 {
+            return Err((LexErrorKind::MissingDigits, 0, index));
+        }
+
+        // Fractional part
+        let fraction_start = index;
+        if raw[index..].starts_with('.') {
+            if exponent_marker.is_none() {
+                return Err((LexErrorKind::UnexpectedDecimalPoint, index, index + 1));
+            }
+
+            index += 1;
+            is_float = true;
+            if Self::parse_digits(raw, &mut index, radix)? == 0 {
+                return Err((LexErrorKind::MissingDigits, fraction_start, index));
+            }
+        }
+
+        // Exponent
+        let exponent_start = index;
+        let has_exponent = raw[index..]
+            .chars()
+            .next()
+            .is_some_and(|c| Some(c.to_ascii_lowercase()) == exponent_marker);
+        if has_exponent {
+            index += 1;
+            if raw[index..].starts_with(['+', '-']) {
+                index += 1;
+            }
+
+            is_float = true;
+            if Self::parse_digits(raw, &mut index, 10)? == 0 {
+                return Err((LexErrorKind::MissingDigits, exponent_start, index));
+            }
+        } else if is_float && format == NumberFormat::Hex {
+            // Hexadecimal floats require an exponent
+            return Err((LexErrorKind::MissingExponent, fraction_start, index));
+        }
+
+        if raw[index..].starts_with('.') {
+            return Err((LexErrorKind::UnexpectedDecimalPoint, index, index + 1));
+        }
+
+        // The value has no prefix, separators or suffix,
+        // and its exponent marker is always lowercase
+        let value = &raw[digits_start..index];
+        let value: Cow<'src, str> = if value.contains('_')
+            || (has_exponent && raw.as_bytes()[exponent_start].is_ascii_uppercase())
+        {
+            Cow::Owned(
+                value
+                    .char_indices()
+                    .filter(|(_, c)| *c != '_')
+                    .map(|(i, c)| match digits_start + i == exponent_start {
+                        true => c.to_ascii_lowercase(),
+                        false => c,
+                    })
+                    .collect(),
+            )
+        } else {
+            Cow::Borrowed(value)
+        };
+
+        // Suffix
+        let suffix = &raw[index..];
+        if suffix.is_empty() {
+            return Ok(Token::Number {
+                value,
+                format,
+                is_float,
+                suffix: None,
+            });
+        }
+
+        let suffix_is_float = match NUMBER_SUFFIXES.get(suffix) {
+            Some(&true) if format != NumberFormat::Decimal => None,
+            Some(&false) if is_float => None,
+            suffix_is_float => suffix_is_float.copied(),
+        };
+        let Some(suffix_is_float) = suffix_is_float else {
+            return Err((
+                LexErrorKind::InvalidSuffix(suffix.to_owned()),
+                index,
+                raw.len(),
+            ));
+        };
+
+        Ok(Token::Number {
             value,
             format,
-            is_float,
+            is_float: is_float || suffix_is_float,
+            suffix: Some(suffix),
         })
     }
 
-    fn try_tokenize_symbol(&mut self) -> Option<Token> {
-        let mut symbol_str = String::new();
+    fn try_tokenize_symbol(&mut self) -> Option<Token<'src>> {
+        // Maximal munch: try every candidate length, since
+        // not every prefix of a symbol is a symbol itself
+        let rest = &self.content[self.index..];
         let mut best_match = None;
-        loop {
-            let Some(c) = self.current() else {
-                break;
-            };
-
-            symbol_str.push(c);
-            let Some(symbol) = SYMBOLS.get(&symbol_str) else {
-                break;
-            };
+        for (len, (i, c)) in rest.char_indices().take(MAX_SYMBOL_LEN).enumerate() {
+            if let Some(symbol) = SYMBOLS.get(&rest[..i + c.len_utf8()]) {
+                best_match = Some((symbol.to_owned(), len + 1));
+            }
+        }
 
-            best_match = Some(symbol.to_owned());
+        let (symbol, len) = best_match?;
+        for _ in 0..len {
             self.skip();
         }
 
-        best_match
+        Some(symbol)
     }
 
-    pub fn tokenize(&mut self) -> Option<TokenInfo> {
-        self.skip_whitespaces();
-        let c = self.current()?;
-        let start_pos = self.position();
+    fn tokenize(&mut self) -> Option<TokenInfo<'src>> {
+        let leading_trivia = if self.keep_trivia {
+            self.tokenize_trivia(true)
+        } else {
+            self.skip_whitespaces();
+            vec![]
+        };
+        let start_pos = self.current_position();
+        let Some(c) = self.current() else {
+            if self.emitted_eof {
+                return None;
+            }
+            self.emitted_eof = true;
+
+            return Some(TokenInfo {
+                token: Token::EOF,
+                start_pos: start_pos.clone(),
+                end_pos: start_pos,
+                leading_trivia,
+                trailing_trivia: vec![],
+            });
+        };
 
         let symbol_token = self.try_tokenize_symbol();
 
         let token = match c {
             _ if symbol_token.is_some() => symbol_token.unwrap(),
-            '"' => self.tokenize_string()?,
+            '"' => self.tokenize_string(StringPrefix::None, start_pos.clone())?,
+            '\'' => self.tokenize_char()?,
+            '#' if self.is_shebang() => self.tokenize_shebang()?,
+            '#' => self.tokenize_comment()?,
             '0'..='9' => self.tokenize_number()?,
-            'a'..='z' | 'A'..='Z' | '_' => self.tokenize_identifier_or_keyword()?,
+            _ if c == '_' || is_xid_start(c) => self.tokenize_identifier_or_keyword()?,
             _ => {
                 self.skip();
-                Token::Unknown(c.to_string())
+                self.error(
+                    LexErrorKind::UnexpectedChar(c),
+                    start_pos.clone(),
+                    self.current_position(),
+                );
+                Token::Invalid
             }
         };
 
-        let end_pos = self.position();
+        let end_pos = self.current_position();
+        let trailing_trivia = if self.keep_trivia {
+            self.tokenize_trivia(false)
+        } else {
+            vec![]
+        };
 
         Some(TokenInfo {
             token,
             start_pos,
             end_pos,
+            leading_trivia,
+            trailing_trivia,
         })
     }
 }
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = TokenInfo<'src>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.tokenize()
+    }
+}
@@ -1,11 +1,15 @@
 mod lexer;
+mod line_index;
 mod parser;
 mod semantic;
+mod source;
 mod syntax;
 mod token;
 
 pub use lexer::*;
+pub use line_index::*;
 pub use parser::*;
 pub use semantic::*;
+pub use source::*;
 pub use syntax::*;
 pub use token::*;
@@ -0,0 +1,167 @@
+use unicode_width::UnicodeWidthChar;
+
+/// Zero-based line and column, as used by editors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Bytes
+    Utf8,
+    /// UTF-16 code units, as counted by most editors
+    Utf16,
+    /// Terminal cells, with tabs advancing to the next tab stop
+    Display { tab_width: usize },
+}
+
+// Character whose width is not one in every column unit
+#[derive(Debug, Clone)]
+struct WideChar {
+    // Byte offset from the start of its line
+    start: usize,
+    len_utf8: usize,
+    len_utf16: usize,
+    // None for tabs, whose width depends on their column
+    display_width: Option<usize>,
+}
+
+impl WideChar {
+    fn end(&self) -> usize {
+        self.start + self.len_utf8
+    }
+
+    fn len(&self, unit: ColumnUnit, column: usize) -> usize {
+        match (unit, self.display_width) {
+            (ColumnUnit::Utf8, _) => self.len_utf8,
+            (ColumnUnit::Utf16, _) => self.len_utf16,
+            (ColumnUnit::Display { .. }, Some(width)) => width,
+            (ColumnUnit::Display { tab_width }, None) => {
+                let tab_width = tab_width.max(1);
+                tab_width - column % tab_width
+            }
+        }
+    }
+}
+
+/// Maps byte offsets of a source to lines and columns, and back
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    // Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    wide_chars: Vec<Vec<WideChar>>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = vec![vec![]];
+        for (i, c) in text.char_indices() {
+            let line_start = *line_starts.last().expect("Missing line start");
+            if c == '\n' {
+                line_starts.push(i + 1);
+                wide_chars.push(vec![]);
+                continue;
+            }
+
+            if c.is_ascii() && c != '\t' {
+                continue;
+            }
+
+            wide_chars
+                .last_mut()
+                .expect("Missing line wide chars")
+                .push(WideChar {
+                    start: i - line_start,
+                    len_utf8: c.len_utf8(),
+                    len_utf16: c.len_utf16(),
+                    display_width: match c {
+                        '\t' => None,
+                        _ => Some(c.width().unwrap_or(0)),
+                    },
+                });
+        }
+
+        Self {
+            line_starts,
+            wide_chars,
+            len: text.len(),
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    // Length of a line in bytes, without its line break
+    fn line_len(&self, line: usize) -> usize {
+        match self.line_starts.get(line + 1) {
+            Some(next_start) => next_start - 1 - self.line_starts[line],
+            None => self.len - self.line_starts[line],
+        }
+    }
+
+    /// Converts a byte offset into a line and column. Offsets inside a
+    /// character resolve to its start, and offsets past the end to the end.
+    pub fn line_col(&self, offset: usize, unit: ColumnUnit) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&x| x <= offset) - 1;
+        let line_offset = offset - self.line_starts[line];
+
+        let mut column = 0;
+        let mut last_end = 0;
+        for wide_char in &self.wide_chars[line] {
+            if wide_char.end() > line_offset {
+                break;
+            }
+
+            column += wide_char.start - last_end;
+            column += wide_char.len(unit, column);
+            last_end = wide_char.end();
+        }
+
+        // Snap to the start of the character containing the offset
+        let line_offset = self.wide_chars[line]
+            .iter()
+            .find(|x| x.start < line_offset && line_offset < x.end())
+            .map_or(line_offset, |x| x.start);
+        column += line_offset - last_end;
+
+        LineCol { line, column }
+    }
+
+    /// Converts a line and column into a byte offset. Columns inside a
+    /// character resolve to its start. Returns None if the line or
+    /// column are past the end of the source or line.
+    pub fn offset(&self, line_col: LineCol, unit: ColumnUnit) -> Option<usize> {
+        let line_start = *self.line_starts.get(line_col.line)?;
+        let line_len = self.line_len(line_col.line);
+
+        let mut column = 0;
+        let mut last_end = 0;
+        for wide_char in &self.wide_chars[line_col.line] {
+            // Narrow characters before this one
+            if line_col.column < column + wide_char.start - last_end {
+                break;
+            }
+            column += wide_char.start - last_end;
+
+            let len = wide_char.len(unit, column);
+            if line_col.column < column + len {
+                return Some(line_start + wide_char.start);
+            }
+            column += len;
+            last_end = wide_char.end();
+        }
+
+        let line_offset = last_end + (line_col.column - column);
+        if line_offset > line_len {
+            return None;
+        }
+
+        Some(line_start + line_offset)
+    }
+}
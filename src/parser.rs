@@ -1,10 +1,10 @@
 use crate::{
-    CodeBlock, Expression, FunctionDefinition, FunctionParam, Node, NodeInfo, Position, Statement,
-    Token, TokenInfo, Type,
+    BinaryOperator, CodeBlock, Expression, FunctionDefinition, FunctionParam, Node, NodeInfo, Span,
+    Statement, Token, TokenInfo, Type, UnaryOperator,
 };
 
-pub struct Parser {
-    tokens: Vec<TokenInfo>,
+pub struct Parser<'src> {
+    tokens: Vec<TokenInfo<'src>>,
     index: usize,
 }
 
@@ -14,7 +14,7 @@ macro_rules! advance_expected {
         $parser.skip(1);
         let token_info = $parser.peek_token_info(old_index);
         let Token::$variant = token_info.token.to_owned() else {
-            return Err(format!("({}@{}:{}) expected {} at '{:?}', found: {:?}", file!(), line!(), column!(), stringify!($variant), token_info.start_pos, token_info.token));
+            return Err(format!("({}@{}:{}) expected {} at '{:?}', found: '{}'", file!(), line!(), column!(), stringify!($variant), token_info.start_pos, token_info.token));
         };
     };
 
@@ -23,7 +23,7 @@ macro_rules! advance_expected {
         $parser.skip(1);
         let token_info = $parser.peek_token_info(old_index);
         let Token::$variant($($field),+) = token_info.token.to_owned() else {
-            return Err(format!("({}@{}:{}) expected {} at '{:?}', found: {:?}", file!(), line!(), column!(), stringify!($variant), token_info.start_pos, token_info.token));
+            return Err(format!("({}@{}:{}) expected {} at '{:?}', found: '{}'", file!(), line!(), column!(), stringify!($variant), token_info.start_pos, token_info.token));
         };
     };
 }
@@ -31,7 +31,7 @@ macro_rules! advance_expected {
 macro_rules! unexpected_token {
     ($token_info:ident) => {
         return Err(format!(
-            "({}@{}:{}) unexpected token at '{:?}': {:?}",
+            "({}@{}:{}) unexpected token at '{:?}': '{}'",
             file!(),
             line!(),
             column!(),
@@ -42,31 +42,39 @@ macro_rules! unexpected_token {
 }
 
 #[allow(dead_code)] // Allow helper functions to exist without warnings
-impl Parser {
-    pub fn new(mut tokens: Vec<TokenInfo>) -> Self {
-        // Hack for making up for lack of EOF in tokenizer
-        let eof_pos = tokens
-            .last()
-            .map(|x| x.end_pos.to_owned())
-            .unwrap_or(Position {
-                index: 0,
-                line: 1,
-                column: 1,
+impl<'src> Parser<'src> {
+    /// Create a parser over a token stream. Streams that don't end in EOF
+    /// get one after their last token, but they can't be empty, since
+    /// there would be no position for it.
+    pub fn new(tokens: impl IntoIterator<Item = TokenInfo<'src>>) -> Result<Self, String> {
+        // Comments carry no meaning for the parser
+        let mut tokens = tokens
+            .into_iter()
+            .filter(|x| !matches!(x.token, Token::Comment(_) | Token::Shebang(_)))
+            .collect::<Vec<TokenInfo<'src>>>();
+
+        let Some(last) = tokens.last() else {
+            return Err("token stream is empty".to_owned());
+        };
+        if last.token != Token::EOF {
+            let end_pos = last.end_pos.clone();
+            tokens.push(TokenInfo {
+                token: Token::EOF,
+                start_pos: end_pos.clone(),
+                end_pos,
+                leading_trivia: vec![],
+                trailing_trivia: vec![],
             });
-        tokens.push(TokenInfo {
-            token: Token::EOF,
-            start_pos: eof_pos.clone(),
-            end_pos: eof_pos,
-        });
+        }
 
-        Self { tokens, index: 0 }
+        Ok(Self { tokens, index: 0 })
     }
 
     fn max_index(&self) -> usize {
         self.tokens.len() - 1
     }
 
-    pub fn tokens(&self) -> &Vec<TokenInfo> {
+    pub fn tokens(&self) -> &Vec<TokenInfo<'src>> {
         &self.tokens
     }
 
@@ -78,34 +86,34 @@ impl Parser {
         self.set_index(self.index + count);
     }
 
-    fn peek_token_info(&self, index: usize) -> &TokenInfo {
+    fn peek_token_info(&self, index: usize) -> &TokenInfo<'src> {
         self.tokens
             .get(index.min(self.max_index()))
             .expect("No tokens to parse")
     }
 
-    fn current_token_info(&self) -> &TokenInfo {
+    fn current_token_info(&self) -> &TokenInfo<'src> {
         self.peek_token_info(self.index)
     }
 
-    fn advance_token_info(&mut self) -> &TokenInfo {
+    fn advance_token_info(&mut self) -> &TokenInfo<'src> {
         let old_index = self.index;
         self.skip(1);
         self.peek_token_info(old_index)
     }
 
-    fn peek(&self, index: usize) -> Token {
-        self.peek_token_info(index).token.to_owned()
+    fn peek(&self, index: usize) -> &Token<'src> {
+        &self.peek_token_info(index).token
     }
 
-    fn current(&self) -> Token {
+    fn current(&self) -> &Token<'src> {
         self.peek(self.index)
     }
 
-    fn advance(&mut self) -> Token {
+    fn advance(&mut self) -> Token<'src> {
         let old_index = self.index;
         self.skip(1);
-        self.peek(old_index)
+        self.peek(old_index).to_owned()
     }
 
     fn parse_function_params(&mut self) -> Result<Vec<FunctionParam>, String> {
@@ -118,7 +126,7 @@ impl Parser {
                 break;
             };
 
-            if params.len() > 0 {
+            if !params.is_empty() {
                 advance_expected!(self, Comma);
             }
 
@@ -127,7 +135,7 @@ impl Parser {
 
             let declared_type = self.parse_type()?;
             params.push(FunctionParam {
-                name,
+                name: name.into_owned(),
                 declared_type,
             });
         }
@@ -137,62 +145,266 @@ impl Parser {
 
     fn parse_type(&mut self) -> Result<Type, String> {
         let token_info = self.advance_token_info();
-        let result = match &token_info.token {
-            Token::Identifier(ident) => Ok(Type::Common(ident.to_owned())),
+        match &token_info.token {
+            Token::Identifier(ident) => Ok(Type::Common(ident.to_string())),
             Token::Times => Ok(Type::Pointer(Box::new(self.parse_type()?))),
             Token::KwFn => {
                 advance_expected!(self, LeftParen);
                 let params = self.parse_function_params()?;
                 let save_index = self.index;
-                let return_type = self
-                    .parse_type()
-                    .ok()
-                    .and_then(|x| Some(Box::new(x)))
-                    .or_else(|| {
-                        self.set_index(save_index);
-                        None
-                    });
+                let return_type = self.parse_type().ok().map(Box::new).or_else(|| {
+                    self.set_index(save_index);
+                    None
+                });
                 Ok(Type::Function {
                     params,
                     return_type,
                 })
             }
             _ => unexpected_token!(token_info),
-        };
-        result
+        }
     }
 
+    // After `a :`, either `T` or `T = e`
     fn parse_declaration(&mut self, ident: String) -> Result<Node, String> {
+        let start_index = self.index;
         let declared_type = self.parse_type()?;
-        advance_expected!(self, SemiColon);
+        let type_span = Span {
+            start_index,
+            end_index: self.index,
+        };
 
-        Ok(Node::Expression(Expression::Declare {
+        let (value, value_span) = if let Token::Assign = self.current() {
+            self.skip(1);
+            let (value, value_span) = self.parse_spanned_expression()?;
+            (Some(Box::new(value)), Some(value_span))
+        } else {
+            (None, None)
+        };
+
+        Ok(Node::Expression(Expression::Declaration {
             identifier: ident,
-            declared_type,
+            declared_type: Some(declared_type),
+            type_span: Some(type_span),
+            value,
+            value_span,
         }))
     }
 
+    // After `a :=`
+    fn parse_inferred_declaration(&mut self, ident: String) -> Result<Node, String> {
+        let (value, value_span) = self.parse_spanned_expression()?;
+
+        Ok(Node::Expression(Expression::Declaration {
+            identifier: ident,
+            declared_type: None,
+            type_span: None,
+            value: Some(Box::new(value)),
+            value_span: Some(value_span),
+        }))
+    }
+
+    fn parse_spanned_expression(&mut self) -> Result<(Expression, Span), String> {
+        let start_index = self.index;
+        let expression = self.parse_expression()?;
+
+        Ok((
+            expression,
+            Span {
+                start_index,
+                end_index: self.index,
+            },
+        ))
+    }
+
     fn parse_parenthesis_expression(&mut self) -> Result<Expression, String> {
-        Err("parenthesis expression not implemented".to_owned())
+        if self.is_lambda_start() {
+            return self.parse_lambda();
+        }
+
+        let expression = self.parse_expression()?;
+        advance_expected!(self, RightParen);
+
+        Ok(Expression::Paren(Box::new(expression)))
+    }
+
+    fn binary_operator(token: &Token) -> Option<BinaryOperator> {
+        match token {
+            Token::Plus => Some(BinaryOperator::Add),
+            Token::Minus => Some(BinaryOperator::Subtract),
+            Token::Times => Some(BinaryOperator::Multiply),
+            Token::DividedBy => Some(BinaryOperator::Divide),
+            Token::Modulo => Some(BinaryOperator::Modulo),
+            Token::Equals => Some(BinaryOperator::Equals),
+            Token::NotEquals => Some(BinaryOperator::NotEquals),
+            Token::LessThan => Some(BinaryOperator::LessThan),
+            Token::LessThanOrEquals => Some(BinaryOperator::LessThanOrEquals),
+            Token::GreaterThan => Some(BinaryOperator::GreaterThan),
+            Token::GreaterThanOrEquals => Some(BinaryOperator::GreaterThanOrEquals),
+            Token::LogicalAnd => Some(BinaryOperator::LogicalAnd),
+            Token::LogicalOr => Some(BinaryOperator::LogicalOr),
+            Token::BitwiseAnd => Some(BinaryOperator::BitwiseAnd),
+            Token::BitwiseOr => Some(BinaryOperator::BitwiseOr),
+            Token::BitwiseXor => Some(BinaryOperator::BitwiseXor),
+            Token::ShiftLeft => Some(BinaryOperator::ShiftLeft),
+            Token::ShiftRight => Some(BinaryOperator::ShiftRight),
+            _ => None,
+        }
+    }
+
+    // `Some(None)` for a plain assignment
+    fn assignment_operator(token: &Token) -> Option<Option<BinaryOperator>> {
+        match token {
+            Token::Assign => Some(None),
+            Token::PlusAssign => Some(Some(BinaryOperator::Add)),
+            Token::MinusAssign => Some(Some(BinaryOperator::Subtract)),
+            Token::TimesAssign => Some(Some(BinaryOperator::Multiply)),
+            Token::DividedByAssign => Some(Some(BinaryOperator::Divide)),
+            Token::ModuloAssign => Some(Some(BinaryOperator::Modulo)),
+            Token::BitwiseAndAssign => Some(Some(BinaryOperator::BitwiseAnd)),
+            Token::BitwiseOrAssign => Some(Some(BinaryOperator::BitwiseOr)),
+            Token::BitwiseXorAssign => Some(Some(BinaryOperator::BitwiseXor)),
+            Token::ShiftLeftAssign => Some(Some(BinaryOperator::ShiftLeft)),
+            Token::ShiftRightAssign => Some(Some(BinaryOperator::ShiftRight)),
+            _ => None,
+        }
+    }
+
+    fn unary_operator(token: &Token) -> Option<UnaryOperator> {
+        match token {
+            Token::Minus => Some(UnaryOperator::Negate),
+            Token::LogicalNot => Some(UnaryOperator::LogicalNot),
+            Token::BitwiseNot => Some(UnaryOperator::BitwiseNot),
+            Token::Times => Some(UnaryOperator::Dereference),
+            Token::BitwiseAnd => Some(UnaryOperator::AddressOf),
+            _ => None,
+        }
     }
 
     fn parse_expression(&mut self) -> Result<Expression, String> {
+        self.parse_binary_expression(0)
+    }
+
+    // Precedence climbing: only operators that bind at least
+    // as tight as `min_precedence` are part of this expression
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, String> {
+        let mut lhs = self.parse_unary_expression()?;
+        while !Self::ends_with_lambda(&lhs)
+            && let Some(op) = Self::binary_operator(self.current())
+            && op.precedence() >= min_precedence
+        {
+            self.skip(1);
+            // Left associative, so the right side binds tighter
+            let rhs = self.parse_binary_expression(op.precedence() + 1)?;
+            lhs = Expression::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    // The code of a lambda ends the expression, like the code of a function
+    // ends its definition, so e.g. `f := () {}` can be followed by `*p = 1;`
+    fn ends_with_lambda(expression: &Expression) -> bool {
+        match expression {
+            Expression::AnonymousFunctionDefinition { .. } => true,
+            Expression::Unary { operand, .. } => Self::ends_with_lambda(operand),
+            Expression::Binary { rhs, .. } => Self::ends_with_lambda(rhs),
+            _ => false,
+        }
+    }
+
+    fn parse_unary_expression(&mut self) -> Result<Expression, String> {
+        let Some(op) = Self::unary_operator(self.current()) else {
+            return self.parse_postfix_expression();
+        };
+
+        self.skip(1);
+        Ok(Expression::Unary {
+            op,
+            operand: Box::new(self.parse_unary_expression()?),
+        })
+    }
+
+    // Field accesses and indexing
+    fn parse_postfix_expression(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_primary_expression()?;
+        if Self::ends_with_lambda(&expression) {
+            return Ok(expression);
+        }
+
+        loop {
+            match self.current() {
+                Token::Dot => {
+                    self.skip(1);
+                    advance_expected!(self, Identifier, field);
+                    expression = Expression::Field {
+                        base: Box::new(expression),
+                        field: field.into_owned(),
+                    };
+                }
+                Token::LeftBracket => {
+                    self.skip(1);
+                    let index = self.parse_expression()?;
+                    advance_expected!(self, RightBracket);
+                    expression = Expression::Index {
+                        base: Box::new(expression),
+                        index: Box::new(index),
+                    };
+                }
+                _ => return Ok(expression),
+            }
+        }
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expression, String> {
         let token_info = self.advance_token_info();
         match token_info.token {
             Token::LeftParen => self.parse_parenthesis_expression(),
-            Token::String(ref s) => Ok(Expression::String(s.to_owned())),
+            Token::Identifier(ref ident) => {
+                let ident = ident.to_string();
+                if let Token::LeftParen = self.current() {
+                    self.skip(1);
+                    return self.parse_function_call(ident);
+                }
+
+                Ok(Expression::Identifier(ident))
+            }
+            Token::Number {
+                ref value,
+                ref format,
+                is_float,
+                suffix,
+            } => Ok(Expression::Number {
+                value: value.to_string(),
+                format: format.clone(),
+                is_float,
+                suffix: suffix.map(str::to_owned),
+            }),
+            Token::String(ref s) => Ok(Expression::String(s.to_string())),
+            Token::ByteString(ref s) => Ok(Expression::ByteString(s.to_vec())),
+            Token::CString(ref s) => Ok(Expression::CString(s.to_string())),
+            Token::WideString(ref s) => Ok(Expression::WideString(s.to_string())),
+            Token::BinaryString(ref s) => Ok(Expression::BinaryString(s.clone())),
+            Token::Char(c) => Ok(Expression::Char(c)),
+            Token::KwTrue => Ok(Expression::Bool(true)),
+            Token::KwFalse => Ok(Expression::Bool(false)),
+            Token::KwNull => Ok(Expression::Null),
             _ => unexpected_token!(token_info),
         }
     }
 
-    fn parse_function_call(&mut self, fn_ident: String) -> Result<Node, String> {
+    fn parse_function_call(&mut self, fn_ident: String) -> Result<Expression, String> {
         let mut args = vec![];
         if let Token::RightParen = self.current() {
             self.skip(1); // Skip right parenthesis
-            return Ok(Node::Expression(Expression::FunctionCall {
+            return Ok(Expression::FunctionCall {
                 identifier: fn_ident,
                 arguments: args,
-            }));
+            });
         }
         loop {
             let arg = self.parse_expression()?;
@@ -204,25 +416,67 @@ impl Parser {
             advance_expected!(self, Comma);
         }
         self.skip(1); // Skip right parenthesis
-        advance_expected!(self, SemiColon); // TODO: Remove and dont return Node in this function
-        return Ok(Node::Expression(Expression::FunctionCall {
+        Ok(Expression::FunctionCall {
             identifier: fn_ident,
             arguments: args,
-        }));
+        })
     }
 
-    fn parse_identifier(&mut self, ident: String) -> Result<Node, String> {
-        let token_info = self.advance_token_info();
+    // Declaration, assignment or expression, without its semicolon
+    fn parse_simple_statement(&mut self) -> Result<Node, String> {
+        if let Token::Identifier(ident) = self.current() {
+            let ident = ident.to_string();
+            match self.peek(self.index + 1) {
+                Token::Colon => {
+                    self.skip(2);
+                    return self.parse_declaration(ident);
+                }
+                Token::Walrus => {
+                    self.skip(2);
+                    return self.parse_inferred_declaration(ident);
+                }
+                _ => {}
+            }
+        }
 
-        match token_info.token {
-            Token::Colon => self.parse_declaration(ident),
-            Token::Walrus => Ok(Node::Expression(Expression::DeclareAndAssign {
-                identifier: ident,
-                value: Box::new(self.parse_expression()?),
-            })),
-            Token::LeftParen => self.parse_function_call(ident),
-            _ => unexpected_token!(token_info),
+        let target = self.parse_expression()?;
+        let Some(op) = Self::assignment_operator(self.current()) else {
+            return Ok(Node::Expression(target));
+        };
+
+        if !target.is_place() {
+            let token_info = self.current_token_info();
+            return Err(format!(
+                "({}@{}:{}) cannot assign to expression at '{:?}': '{}'",
+                file!(),
+                line!(),
+                column!(),
+                token_info.start_pos,
+                token_info.token
+            ));
         }
+
+        self.skip(1);
+        let value = self.parse_expression()?;
+
+        Ok(Node::Statement(Statement::Assignment { target, op, value }))
+    }
+
+    fn parse_simple_statement_node(&mut self) -> Result<Node, String> {
+        let node = self.parse_simple_statement()?;
+
+        // Like other definitions, lambdas don't need a semicolon after their code
+        if let Node::Expression(Expression::Declaration {
+            value: Some(value), ..
+        }) = &node
+            && let Expression::AnonymousFunctionDefinition { .. } = **value
+            && !matches!(self.current(), Token::SemiColon)
+        {
+            return Ok(node);
+        }
+
+        advance_expected!(self, SemiColon);
+        Ok(node)
     }
 
     fn parse_extern(&mut self) -> Result<Node, String> {
@@ -232,7 +486,7 @@ impl Parser {
         advance_expected!(self, SemiColon);
 
         Ok(Node::Statement(Statement::Extern {
-            identifier: ident,
+            identifier: ident.into_owned(),
             declared_type: decl_type,
         }))
     }
@@ -262,50 +516,145 @@ impl Parser {
         Ok(Node::Statement(Statement::CodeBlock(codeblock)))
     }
 
-    fn parse_function(&mut self) -> Result<FunctionDefinition, String> {
-        let token_info = self.advance_token_info().clone();
-        Ok(match token_info.token {
-            Token::Identifier(ident) => {
-                advance_expected!(self, LeftParen);
-                let params = self.parse_function_params()?;
-                let return_type = if !matches!(self.current(), Token::LeftCurly) {
-                    Some(self.parse_type()?)
-                } else {
-                    None
-                };
+    fn parse_if(&mut self) -> Result<Statement, String> {
+        let condition = self.parse_expression()?;
+        advance_expected!(self, LeftCurly);
+        let code = self.parse_codeblock()?;
+
+        let else_branch = if let Token::KwElse = self.current() {
+            self.skip(1);
+            if let Token::KwIf = self.current() {
+                self.skip(1);
+                Some(Box::new(self.parse_if()?))
+            } else {
                 advance_expected!(self, LeftCurly);
-                let code = self.parse_codeblock()?;
-                FunctionDefinition {
-                    identifier: Some(ident.to_owned()),
-                    params,
-                    return_type,
-                    code,
-                }
+                Some(Box::new(Statement::CodeBlock(self.parse_codeblock()?)))
             }
-            _ => unexpected_token!(token_info),
+        } else {
+            None
+        };
+
+        Ok(Statement::If {
+            condition,
+            code,
+            else_branch,
         })
     }
 
-    fn parse_function_definition(&mut self) -> Result<Node, String> {
-        let function = self.parse_function()?;
-        Ok(match function.identifier {
-            Some(identifier) => Node::Statement(Statement::FunctionDefinition {
-                identifier,
-                params: function.params,
-                return_type: function.return_type,
-                code: function.code,
-            }),
-            None => {
-                // Anonymous function declarations are expressions (lambda fn)
-                Node::Expression(Expression::AnonymousFunctionDefinition {
-                    params: function.params,
-                    return_type: function.return_type,
-                    code: function.code,
-                })
-            }
+    fn parse_while(&mut self) -> Result<Node, String> {
+        let condition = self.parse_expression()?;
+        advance_expected!(self, LeftCurly);
+        let code = self.parse_codeblock()?;
+
+        Ok(Node::Statement(Statement::While { condition, code }))
+    }
+
+    // for init; condition; step { ... }
+    fn parse_for(&mut self) -> Result<Node, String> {
+        let init = match self.current() {
+            Token::SemiColon => None,
+            _ => Some(Box::new(self.parse_simple_statement()?)),
+        };
+        advance_expected!(self, SemiColon);
+
+        let condition = match self.current() {
+            Token::SemiColon => None,
+            _ => Some(self.parse_expression()?),
+        };
+        advance_expected!(self, SemiColon);
+
+        let step = match self.current() {
+            Token::LeftCurly => None,
+            _ => Some(Box::new(self.parse_simple_statement()?)),
+        };
+        advance_expected!(self, LeftCurly);
+        let code = self.parse_codeblock()?;
+
+        Ok(Node::Statement(Statement::For {
+            init,
+            condition,
+            step,
+            code,
+        }))
+    }
+
+    // return; / return expr;
+    fn parse_return(&mut self) -> Result<Node, String> {
+        let value = match self.current() {
+            Token::SemiColon => None,
+            _ => Some(self.parse_expression()?),
+        };
+        advance_expected!(self, SemiColon);
+
+        Ok(Node::Statement(Statement::Return { value }))
+    }
+
+    // break; / continue;
+    fn parse_jump(&mut self, statement: Statement) -> Result<Node, String> {
+        advance_expected!(self, SemiColon);
+        Ok(Node::Statement(statement))
+    }
+
+    fn parse_loop(&mut self) -> Result<Node, String> {
+        advance_expected!(self, LeftCurly);
+        let code = self.parse_codeblock()?;
+
+        Ok(Node::Statement(Statement::Loop { code }))
+    }
+
+    // Parameters, return type and code of a function, after its `(`
+    fn parse_function_rest(&mut self) -> Result<FunctionDefinition, String> {
+        let params = self.parse_function_params()?;
+        let return_type = if !matches!(self.current(), Token::LeftCurly) {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        advance_expected!(self, LeftCurly);
+        let code = self.parse_codeblock()?;
+
+        Ok(FunctionDefinition {
+            identifier: None,
+            params,
+            return_type,
+            code,
+        })
+    }
+
+    // A parameter list is either empty or starts with `name :`, which
+    // no parenthesized expression does, so two tokens are enough to tell
+    fn is_lambda_start(&self) -> bool {
+        match self.current() {
+            Token::RightParen => true,
+            Token::Identifier(_) => matches!(self.peek(self.index + 1), Token::Colon),
+            _ => false,
+        }
+    }
+
+    // After `(`, e.g. `(argc: i32, argv: **char) i32 { ... }`
+    fn parse_lambda(&mut self) -> Result<Expression, String> {
+        let function = self.parse_function_rest()?;
+
+        Ok(Expression::AnonymousFunctionDefinition {
+            params: function.params,
+            return_type: function.return_type,
+            code: function.code,
         })
     }
 
+    fn parse_function_definition(&mut self) -> Result<Node, String> {
+        advance_expected!(self, Identifier, identifier);
+        advance_expected!(self, LeftParen);
+        let function = self.parse_function_rest()?;
+
+        Ok(Node::Statement(Statement::FunctionDefinition {
+            identifier: identifier.into_owned(),
+            params: function.params,
+            return_type: function.return_type,
+            code: function.code,
+        }))
+    }
+
     // Puts the parser in a good spot
     // after parsing a bad node
     fn resynchronize(&mut self) {
@@ -331,11 +680,21 @@ impl Parser {
         let result = match token_info.token.to_owned() {
             Token::KwExtern => self.parse_extern(),
             Token::KwFn => self.parse_function_definition(),
-            Token::Identifier(ident) => self.parse_identifier(ident),
+            Token::Identifier(_) | Token::Times | Token::LeftParen => {
+                self.set_index(start_index);
+                self.parse_simple_statement_node()
+            }
             Token::LeftCurly => self.parse_codeblock_node(),
+            Token::KwIf => self.parse_if().map(Node::Statement),
+            Token::KwWhile => self.parse_while(),
+            Token::KwFor => self.parse_for(),
+            Token::KwLoop => self.parse_loop(),
+            Token::KwReturn => self.parse_return(),
+            Token::KwBreak => self.parse_jump(Statement::Break),
+            Token::KwContinue => self.parse_jump(Statement::Continue),
             Token::EOF => return None,
             _ => Err(format!(
-                "({}@{}:{}) invalid root token at '{:?}': {:?}",
+                "({}@{}:{}) invalid root token at '{:?}': '{}'",
                 file!(),
                 line!(),
                 column!(),
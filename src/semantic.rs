@@ -17,8 +17,22 @@ pub struct SemanticFunctionParam {
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SemanticType {
     Int8,
+    Int16,
     Int32,
+    Int64,
+    ISize,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    USize,
+    Float32,
+    Float64,
     Pointer(Box<SemanticType>),
+    Array {
+        element: Box<SemanticType>,
+        length: usize,
+    },
     Function {
         params: Vec<SemanticFunctionParam>,
         return_type: Option<Box<SemanticType>>,
@@ -29,8 +43,20 @@ impl TryFrom<Type> for SemanticType {
     type Error = String;
 
     fn try_from(value: Type) -> Result<Self, Self::Error> {
-        let mappings: HashMap<&'static str, SemanticType> =
-            HashMap::from([("i8", SemanticType::Int8), ("i32", SemanticType::Int32)]);
+        let mappings: HashMap<&'static str, SemanticType> = HashMap::from([
+            ("i8", SemanticType::Int8),
+            ("i16", SemanticType::Int16),
+            ("i32", SemanticType::Int32),
+            ("i64", SemanticType::Int64),
+            ("isize", SemanticType::ISize),
+            ("u8", SemanticType::UInt8),
+            ("u16", SemanticType::UInt16),
+            ("u32", SemanticType::UInt32),
+            ("u64", SemanticType::UInt64),
+            ("usize", SemanticType::USize),
+            ("f32", SemanticType::Float32),
+            ("f64", SemanticType::Float64),
+        ]);
 
         match value {
             Type::Common(t) => mappings
@@ -46,11 +72,9 @@ impl TryFrom<Type> for SemanticType {
                 params,
                 return_type,
             } => {
-                let sem_ret_type = if let Some(ret_type) = return_type {
-                    Self::try_from(*ret_type).map(|x| Box::new(x)).ok()
-                } else {
-                    None
-                };
+                let sem_ret_type = return_type
+                    .map(|x| Self::try_from(*x).map(Box::new))
+                    .transpose()?;
 
                 let sem_params = params
                     .into_iter()
@@ -71,6 +95,80 @@ impl TryFrom<Type> for SemanticType {
     }
 }
 
+impl SemanticType {
+    /// Type of a literal expression, `None` if it isn't a literal.
+    ///
+    /// Numbers have the type of their suffix, and are `i32` or `f64`
+    /// without one, like in C. Plain strings are UTF-8 text, so they are
+    /// arrays of `i8` like C's `char`. Byte and binary strings are both
+    /// arrays of raw `u8`, as they only differ in how they are written.
+    pub fn of_literal(expr: &Expression) -> Option<Self> {
+        let array = |element, length| SemanticType::Array {
+            element: Box::new(element),
+            length,
+        };
+
+        match expr {
+            // The lexer only accepts suffixes that are type names
+            Expression::Number {
+                suffix: Some(suffix),
+                ..
+            } => Self::try_from(Type::Common(suffix.clone())).ok(),
+            Expression::Number { is_float, .. } => match is_float {
+                true => Some(SemanticType::Float64),
+                false => Some(SemanticType::Int32),
+            },
+            Expression::String(s) => Some(array(SemanticType::Int8, s.len())),
+            Expression::ByteString(bytes) | Expression::BinaryString(bytes) => {
+                Some(array(SemanticType::UInt8, bytes.len()))
+            }
+            // Null terminated strings are passed around as pointers,
+            // for compatibility with C and Windows APIs
+            Expression::CString(_) => Some(SemanticType::Pointer(Box::new(SemanticType::Int8))),
+            Expression::WideString(_) => {
+                Some(SemanticType::Pointer(Box::new(SemanticType::UInt16)))
+            }
+            Expression::Char(_) => Some(SemanticType::Int32),
+            _ => None,
+        }
+    }
+
+    /// Type of a literal expression used where `expected` is. Numbers
+    /// without a suffix take any expected type they can represent.
+    pub fn of_literal_as(expr: &Expression, expected: &Self) -> Option<Self> {
+        match expr {
+            Expression::Number {
+                suffix: None,
+                is_float,
+                ..
+            } if expected.is_float() || (!is_float && expected.is_integer()) => {
+                Some(expected.clone())
+            }
+            _ => Self::of_literal(expr),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            SemanticType::Int8
+                | SemanticType::Int16
+                | SemanticType::Int32
+                | SemanticType::Int64
+                | SemanticType::ISize
+                | SemanticType::UInt8
+                | SemanticType::UInt16
+                | SemanticType::UInt32
+                | SemanticType::UInt64
+                | SemanticType::USize
+        )
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, SemanticType::Float32 | SemanticType::Float64)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SemanticFunctionDefinition {
     pub name: String,
@@ -119,8 +217,7 @@ impl<'a> SemanticAnalyzer<'a> {
         Self { program, nodes }
     }
 
-    fn analyze_extern(&mut self, identifier: String, declared_type: Type) -> Result<(), String> {
-        let sem_type = SemanticType::try_from(declared_type)?;
+    fn declare_symbol(&mut self, identifier: String, sem_type: SemanticType) -> Result<(), String> {
         self.program
             .scopes
             .last_mut()
@@ -136,6 +233,53 @@ impl<'a> SemanticAnalyzer<'a> {
         Ok(())
     }
 
+    fn analyze_extern(&mut self, identifier: String, declared_type: Type) -> Result<(), String> {
+        let sem_type = SemanticType::try_from(declared_type)?;
+        self.declare_symbol(identifier, sem_type)
+    }
+
+    // Only literals and lambdas have a known type for now
+    fn analyze_declaration(
+        &mut self,
+        identifier: String,
+        declared_type: Option<Type>,
+        value: Option<Box<Expression>>,
+    ) -> Result<(), String> {
+        let declared_type = declared_type.map(SemanticType::try_from).transpose()?;
+        let value_type = match value.as_deref() {
+            Some(Expression::AnonymousFunctionDefinition {
+                params,
+                return_type,
+                code,
+            }) => Some(Self::analyze_lambda(
+                &identifier,
+                params,
+                return_type,
+                code,
+            )?),
+            Some(value) => match &declared_type {
+                Some(declared_type) => SemanticType::of_literal_as(value, declared_type),
+                None => SemanticType::of_literal(value),
+            },
+            None => None,
+        };
+
+        let sem_type = match (declared_type, value_type) {
+            (Some(declared_type), Some(value_type)) if declared_type != value_type => {
+                return Err(format!(
+                    "'{}' is declared as '{:?}', but its value is '{:?}'",
+                    identifier, declared_type, value_type
+                ));
+            }
+            (Some(sem_type), _) | (None, Some(sem_type)) => sem_type,
+            (None, None) => {
+                return Err(format!("cannot infer the type of '{}'", identifier));
+            }
+        };
+
+        self.declare_symbol(identifier, sem_type)
+    }
+
     fn analyze_function_definition(
         &mut self,
         identifier: String,
@@ -143,11 +287,9 @@ impl<'a> SemanticAnalyzer<'a> {
         return_type: Option<Type>,
         code: CodeBlock,
     ) -> Result<SemanticFunctionDefinition, String> {
-        let sem_ret_type = if let Some(ret_type) = return_type {
-            SemanticType::try_from(ret_type).ok()
-        } else {
-            None
-        };
+        let sem_ret_type = return_type.map(SemanticType::try_from).transpose()?;
+
+        Self::check_returns(&identifier, &code, sem_ret_type.as_ref())?;
 
         let sem_params = params
             .into_iter()
@@ -188,6 +330,104 @@ impl<'a> SemanticAnalyzer<'a> {
         })
     }
 
+    // Type of a lambda assigned to `identifier`, after checking its returns
+    fn analyze_lambda(
+        identifier: &str,
+        params: &[FunctionParam],
+        return_type: &Option<Type>,
+        code: &CodeBlock,
+    ) -> Result<SemanticType, String> {
+        let sem_type = SemanticType::try_from(Type::Function {
+            params: params.to_vec(),
+            return_type: return_type.clone().map(Box::new),
+        })?;
+        if let SemanticType::Function { return_type, .. } = &sem_type {
+            Self::check_returns(identifier, code, return_type.as_deref())?;
+        }
+
+        Ok(sem_type)
+    }
+
+    // Checks every return statement of a function, including nested ones,
+    // against its return type. Only literal values have a known type for now.
+    fn check_returns(
+        function: &str,
+        code: &CodeBlock,
+        return_type: Option<&SemanticType>,
+    ) -> Result<(), String> {
+        for node in &code.nodes {
+            match &node.node {
+                Node::Statement(statement) => {
+                    Self::check_statement_returns(function, statement, return_type)?;
+                }
+                // Lambdas return from themselves, so they have their own return type
+                Node::Expression(Expression::Declaration {
+                    identifier,
+                    value: Some(value),
+                    ..
+                }) => {
+                    if let Expression::AnonymousFunctionDefinition {
+                        params,
+                        return_type,
+                        code,
+                    } = &**value
+                    {
+                        Self::analyze_lambda(identifier, params, return_type, code)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_statement_returns(
+        function: &str,
+        statement: &Statement,
+        return_type: Option<&SemanticType>,
+    ) -> Result<(), String> {
+        match statement {
+            Statement::Return { value } => match (value, return_type) {
+                (None, None) => {}
+                (Some(_), None) => {
+                    return Err(format!("function '{}' must not return a value", function));
+                }
+                (None, Some(t)) => {
+                    return Err(format!(
+                        "function '{}' must return a value of type '{:?}'",
+                        function, t
+                    ));
+                }
+                (Some(value), Some(t)) => {
+                    if let Some(value_type) = SemanticType::of_literal_as(value, t)
+                        && value_type != *t
+                    {
+                        return Err(format!(
+                            "function '{}' returns '{:?}', expected '{:?}'",
+                            function, value_type, t
+                        ));
+                    }
+                }
+            },
+            Statement::CodeBlock(code)
+            | Statement::While { code, .. }
+            | Statement::For { code, .. }
+            | Statement::Loop { code } => Self::check_returns(function, code, return_type)?,
+            Statement::If {
+                code, else_branch, ..
+            } => {
+                Self::check_returns(function, code, return_type)?;
+                if let Some(else_branch) = else_branch {
+                    Self::check_statement_returns(function, else_branch, return_type)?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     fn analyze_node(&mut self, node: &NodeInfo) -> Result<(), String> {
         match node.node.clone() {
             Node::Statement(Statement::Extern {
@@ -204,6 +444,14 @@ impl<'a> SemanticAnalyzer<'a> {
             }) => {
                 self.analyze_function_definition(identifier, params, return_type, code)?;
             }
+            Node::Expression(Expression::Declaration {
+                identifier,
+                declared_type,
+                value,
+                ..
+            }) => {
+                self.analyze_declaration(identifier, declared_type, value)?;
+            }
 
             n => {
                 return Err(format!("unknown node: {:?}", n));
@@ -0,0 +1,75 @@
+use std::{fs, io, path::Path};
+
+use crate::{LineIndex, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub content: String,
+    // Virtual files only exist in memory
+    pub is_virtual: bool,
+    pub line_index: LineIndex,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<FileId> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        Ok(self.add(path.display().to_string(), content, false))
+    }
+
+    pub fn add_virtual(&mut self, name: impl Into<String>, content: impl Into<String>) -> FileId {
+        self.add(name.into(), content.into(), true)
+    }
+
+    fn add(&mut self, name: String, content: String, is_virtual: bool) -> FileId {
+        let line_index = LineIndex::new(&content);
+        self.files.push(SourceFile {
+            name,
+            content,
+            is_virtual,
+            line_index,
+        });
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        self.files
+            .get(id.0)
+            .expect("File ID does not belong to this source map")
+    }
+
+    pub fn name(&self, id: FileId) -> &str {
+        &self.file(id).name
+    }
+
+    pub fn content(&self, id: FileId) -> &str {
+        &self.file(id).content
+    }
+
+    pub fn line_index(&self, id: FileId) -> &LineIndex {
+        &self.file(id).line_index
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = FileId> {
+        (0..self.files.len()).map(FileId)
+    }
+
+    /// Resolves a position to a `name:line:column` location
+    pub fn location(&self, pos: &Position) -> String {
+        format!("{}:{}:{}", self.name(pos.file), pos.line, pos.column)
+    }
+}
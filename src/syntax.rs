@@ -1,3 +1,5 @@
+use crate::NumberFormat;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FunctionParam {
     pub name: String,
@@ -29,7 +31,7 @@ pub enum Statement {
         declared_type: Type,
     },
     Return {
-        value: Expression,
+        value: Option<Expression>,
     },
     FunctionDefinition {
         identifier: String,
@@ -38,6 +40,33 @@ pub enum Statement {
         code: CodeBlock,
     },
     CodeBlock(CodeBlock),
+    // `target = value`, or `target op= value` for compound assignments
+    Assignment {
+        target: Expression,
+        op: Option<BinaryOperator>,
+        value: Expression,
+    },
+    If {
+        condition: Expression,
+        code: CodeBlock,
+        // Either another `If` (else if) or a `CodeBlock` (else)
+        else_branch: Option<Box<Statement>>,
+    },
+    While {
+        condition: Expression,
+        code: CodeBlock,
+    },
+    For {
+        init: Option<Box<Node>>,
+        condition: Option<Expression>,
+        step: Option<Box<Node>>,
+        code: CodeBlock,
+    },
+    Loop {
+        code: CodeBlock,
+    },
+    Break,
+    Continue,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -45,22 +74,109 @@ pub struct CodeBlock {
     pub nodes: Vec<NodeInfo>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEquals,
+    GreaterThan,
+    GreaterThanOrEquals,
+
+    LogicalAnd,
+    LogicalOr,
+
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+}
+
+impl BinaryOperator {
+    /// Binding power of the operator, higher binds tighter.
+    /// Follows C, and every binary operator is left associative.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::LogicalOr => 1,
+            BinaryOperator::LogicalAnd => 2,
+            BinaryOperator::BitwiseOr => 3,
+            BinaryOperator::BitwiseXor => 4,
+            BinaryOperator::BitwiseAnd => 5,
+            BinaryOperator::Equals | BinaryOperator::NotEquals => 6,
+            BinaryOperator::LessThan
+            | BinaryOperator::LessThanOrEquals
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEquals => 7,
+            BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => 8,
+            BinaryOperator::Add | BinaryOperator::Subtract => 9,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    LogicalNot,
+    BitwiseNot,
+    Dereference,
+    AddressOf,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Expression {
     Identifier(String),
     String(String),
+    ByteString(Vec<u8>),
+    CString(String),
+    WideString(String),
+    BinaryString(Vec<u8>),
+    Char(char),
+    Number {
+        value: String,
+        format: NumberFormat,
+        is_float: bool,
+        suffix: Option<String>,
+    },
+    Bool(bool),
+    Null,
+    Binary {
+        op: BinaryOperator,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
+    },
+    Unary {
+        op: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    Paren(Box<Expression>),
+    Field {
+        base: Box<Expression>,
+        field: String,
+    },
+    Index {
+        base: Box<Expression>,
+        index: Box<Expression>,
+    },
     AnonymousFunctionDefinition {
         params: Vec<FunctionParam>,
         return_type: Option<Type>,
         code: CodeBlock,
     },
-    Declare {
+    // `a : T;`, `a : T = e;` or `a := e;`
+    Declaration {
         identifier: String,
-        declared_type: Type,
-    },
-    DeclareAndAssign {
-        identifier: String,
-        value: Box<Expression>,
+        declared_type: Option<Type>,
+        type_span: Option<Span>,
+        value: Option<Box<Expression>>,
+        value_span: Option<Span>,
     },
     FunctionCall {
         identifier: String,
@@ -68,6 +184,24 @@ pub enum Expression {
     },
 }
 
+impl Expression {
+    /// Whether the expression designates a memory location,
+    /// so that it can be assigned to
+    pub fn is_place(&self) -> bool {
+        match self {
+            Expression::Identifier(_)
+            | Expression::Field { .. }
+            | Expression::Index { .. }
+            | Expression::Unary {
+                op: UnaryOperator::Dereference,
+                ..
+            } => true,
+            Expression::Paren(expression) => expression.is_place(),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Node {
     Invalid,
@@ -75,6 +209,13 @@ pub enum Node {
     Expression(Expression),
 }
 
+// Range of token indices, like the ones of `NodeInfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start_index: usize,
+    pub end_index: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct NodeInfo {
     pub node: Node,
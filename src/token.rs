@@ -1,5 +1,9 @@
+use std::{borrow::Cow, fmt};
+
 use phf::phf_map;
 
+use crate::FileId;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NumberFormat {
     Decimal,
@@ -9,19 +13,40 @@ pub enum NumberFormat {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Token {
+pub enum StringPrefix {
+    None,
+    Byte,
+    C,
+    Utf16,
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'src> {
     EOF,
 
-    Unknown(String),
-    Comment(String),
+    // Lexical error, reported by the lexer
+    Invalid,
+    Comment(&'src str),
+    // `#!` line at the very start of a file, without the `#!`
+    Shebang(&'src str),
 
     // Expressions
-    Identifier(String),
-    String(String),
+    Identifier(Cow<'src, str>),
+    String(Cow<'src, str>),
+    ByteString(Cow<'src, [u8]>),
+    // Null terminated, without the terminator
+    CString(Cow<'src, str>),
+    // Encoded as UTF-16 and null terminated, without the terminator
+    WideString(Cow<'src, str>),
+    // Decoded from the hex digits of a `bin` string
+    BinaryString(Vec<u8>),
+    Char(char),
     Number {
-        value: String,
+        value: Cow<'src, str>,
         format: NumberFormat,
         is_float: bool,
+        suffix: Option<&'src str>,
     },
 
     // Symbols
@@ -37,40 +62,144 @@ pub enum Token {
     LeftCurly,
     RightCurly,
 
+    LeftBracket,
+    RightBracket,
+
     Plus,
     Minus,
     Times,
     DividedBy,
+    Modulo,
+
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEquals,
+    GreaterThan,
+    GreaterThanOrEquals,
+
+    LogicalAnd,
+    LogicalOr,
+    LogicalNot,
 
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseNot,
+    ShiftLeft,
+    ShiftRight,
+
+    PlusAssign,
+    MinusAssign,
+    TimesAssign,
+    DividedByAssign,
+    ModuloAssign,
+    BitwiseAndAssign,
+    BitwiseOrAssign,
+    BitwiseXorAssign,
+    ShiftLeftAssign,
+    ShiftRightAssign,
+
+    Arrow,
+    Dot,
     Comma,
 
     // Keywords
     KwExtern,
     KwReturn,
     KwFn,
+    KwIf,
+    KwElse,
+    KwWhile,
+    KwFor,
+    KwLoop,
+    KwBreak,
+    KwContinue,
+    KwTrue,
+    KwFalse,
+    KwNull,
 }
 
 #[derive(Debug, Clone)]
 pub struct Position {
+    pub file: FileId,
+    /// Byte offset into the source
     pub index: usize,
     pub line: usize,
     pub column: usize,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    Comment,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: &'src str,
+}
+
 #[derive(Debug, Clone)]
-pub struct TokenInfo {
-    pub token: Token,
+pub struct TokenInfo<'src> {
+    pub token: Token<'src>,
     pub start_pos: Position,
     pub end_pos: Position,
+    // Only collected when lexing with trivia
+    pub leading_trivia: Vec<Trivia<'src>>,
+    pub trailing_trivia: Vec<Trivia<'src>>,
 }
 
-pub static KEYWORDS: phf::Map<&'static str, Token> = phf_map! {
+impl TokenInfo<'_> {
+    /// Appends the source text of the token, surrounded by its trivia.
+    /// `source` must be the content the token was lexed from.
+    pub fn write_source(&self, source: &str, out: &mut String) {
+        for trivia in &self.leading_trivia {
+            out.push_str(trivia.text);
+        }
+        out.push_str(&source[self.start_pos.index..self.end_pos.index]);
+        for trivia in &self.trailing_trivia {
+            out.push_str(trivia.text);
+        }
+    }
+}
+
+/// Rebuilds the source of a token stream. Only lossless
+/// for streams lexed with trivia, see `Lexer::with_trivia`.
+pub fn source_of_tokens<'a, 'src: 'a>(
+    tokens: impl IntoIterator<Item = &'a TokenInfo<'src>>,
+    source: &str,
+) -> String {
+    let mut out = String::with_capacity(source.len());
+    for token in tokens {
+        token.write_source(source, &mut out);
+    }
+
+    out
+}
+
+pub static KEYWORDS: phf::Map<&'static str, Token<'static>> = phf_map! {
     "extern" => Token::KwExtern,
     "return" => Token::KwReturn,
-    "fn" => Token::KwFn
+    "fn" => Token::KwFn,
+    "if" => Token::KwIf,
+    "else" => Token::KwElse,
+    "while" => Token::KwWhile,
+    "for" => Token::KwFor,
+    "loop" => Token::KwLoop,
+    "break" => Token::KwBreak,
+    "continue" => Token::KwContinue,
+    "true" => Token::KwTrue,
+    "false" => Token::KwFalse,
+    "null" => Token::KwNull
 };
 
-pub static SYMBOLS: phf::Map<&'static str, Token> = phf_map! {
+// Length in characters of the longest key of `SYMBOLS`
+pub const MAX_SYMBOL_LEN: usize = 3;
+
+pub static SYMBOLS: phf::Map<&'static str, Token<'static>> = phf_map! {
     ";" => Token::SemiColon,
 
     ":" => Token::Colon,
@@ -83,13 +212,224 @@ pub static SYMBOLS: phf::Map<&'static str, Token> = phf_map! {
     "{" => Token::LeftCurly,
     "}" => Token::RightCurly,
 
+    "[" => Token::LeftBracket,
+    "]" => Token::RightBracket,
+
+    "+" => Token::Plus,
+    "-" => Token::Minus,
     "*" => Token::Times,
+    "/" => Token::DividedBy,
+    "%" => Token::Modulo,
+
+    "==" => Token::Equals,
+    "!=" => Token::NotEquals,
+    "<" => Token::LessThan,
+    "<=" => Token::LessThanOrEquals,
+    ">" => Token::GreaterThan,
+    ">=" => Token::GreaterThanOrEquals,
+
+    "&&" => Token::LogicalAnd,
+    "||" => Token::LogicalOr,
+    "!" => Token::LogicalNot,
 
+    "&" => Token::BitwiseAnd,
+    "|" => Token::BitwiseOr,
+    "^" => Token::BitwiseXor,
+    "~" => Token::BitwiseNot,
+    "<<" => Token::ShiftLeft,
+    ">>" => Token::ShiftRight,
+
+    "+=" => Token::PlusAssign,
+    "-=" => Token::MinusAssign,
+    "*=" => Token::TimesAssign,
+    "/=" => Token::DividedByAssign,
+    "%=" => Token::ModuloAssign,
+    "&=" => Token::BitwiseAndAssign,
+    "|=" => Token::BitwiseOrAssign,
+    "^=" => Token::BitwiseXorAssign,
+    "<<=" => Token::ShiftLeftAssign,
+    ">>=" => Token::ShiftRightAssign,
+
+    "->" => Token::Arrow,
+    "." => Token::Dot,
     "," => Token::Comma
 };
 
+pub static STRING_PREFIXES: phf::Map<&'static str, StringPrefix> = phf_map! {
+    "b" => StringPrefix::Byte,
+    "c" => StringPrefix::C,
+    "u16" => StringPrefix::Utf16,
+    "bin" => StringPrefix::Binary,
+};
+
 pub static NUMBER_FORMATS: phf::Map<char, NumberFormat> = phf_map! {
     'x' => NumberFormat::Hex,
     'o' => NumberFormat::Octal,
     'b' => NumberFormat::Binary,
 };
+
+// Maps each numeric literal suffix to whether it is a float type
+pub static NUMBER_SUFFIXES: phf::Map<&'static str, bool> = phf_map! {
+    "i8" => false,
+    "i16" => false,
+    "i32" => false,
+    "i64" => false,
+    "isize" => false,
+    "u8" => false,
+    "u16" => false,
+    "u32" => false,
+    "u64" => false,
+    "usize" => false,
+    "f32" => true,
+    "f64" => true,
+};
+
+impl Token<'_> {
+    fn write_escaped_char(f: &mut fmt::Formatter, c: char, quote: Option<char>) -> fmt::Result {
+        match c {
+            '\\' => write!(f, "\\\\"),
+            '\n' => write!(f, "\\n"),
+            '\t' => write!(f, "\\t"),
+            '\r' => write!(f, "\\r"),
+            '\0' => write!(f, "\\0"),
+            _ if Some(c) == quote => write!(f, "\\{}", c),
+            _ if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32),
+            _ => write!(f, "{}", c),
+        }
+    }
+
+    fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+        let max_quote_run = s.split(|c| c != '"').map(|x| x.len()).max().unwrap_or(0);
+
+        // Strings with quotes use the shortest odd-lengthed quote run
+        // longer than any run in their content, unless a quote would
+        // merge with the string boundaries
+        if max_quote_run > 0 && !s.starts_with('"') && !s.ends_with('"') {
+            let quotes = "\"".repeat((max_quote_run + 1) | 1);
+            write!(f, "{}", quotes)?;
+            for c in s.chars() {
+                Self::write_escaped_char(f, c, None)?;
+            }
+            return write!(f, "{}", quotes);
+        }
+
+        write!(f, "\"")?;
+        for c in s.chars() {
+            Self::write_escaped_char(f, c, Some('"'))?;
+        }
+        write!(f, "\"")
+    }
+
+    fn write_comment(f: &mut fmt::Formatter, comment: &str) -> fmt::Result {
+        if !comment.contains('\n') && !comment.starts_with('#') {
+            return write!(f, "#{}", comment);
+        }
+
+        // Only comes from a first line made only of hashes,
+        // which is where it must be written back
+        if !comment.is_empty() && comment.chars().all(|c| c == '#') {
+            return write!(f, "#{}", comment);
+        }
+
+        // Block comments must not contain a hash run as long as their boundaries
+        let hash_runs = comment
+            .split(|c| c != '#')
+            .map(|x| x.len())
+            .collect::<Vec<usize>>();
+        let hash_count = (2..)
+            .find(|x| !hash_runs.contains(x))
+            .expect("Missing hash count for block comment");
+        let hashes = "#".repeat(hash_count);
+        write!(f, "{}{}{}", hashes, comment, hashes)
+    }
+}
+
+/// Writes tokens back as source text. Invalid tokens and
+/// EOF have no source text, and display as placeholders.
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::EOF => write!(f, "<EOF>"),
+            Token::Invalid => write!(f, "<invalid>"),
+            Token::Comment(comment) => Self::write_comment(f, comment),
+            Token::Shebang(command) => write!(f, "#!{}", command),
+            Token::Identifier(ident) => write!(f, "{}", ident),
+            Token::String(s) => Self::write_string(f, s),
+            Token::ByteString(bytes) => {
+                write!(f, "b\"")?;
+                for byte in bytes.iter() {
+                    match byte {
+                        b'"' | b'\\' => write!(f, "\\{}", *byte as char)?,
+                        0x20..=0x7e => write!(f, "{}", *byte as char)?,
+                        _ => write!(f, "\\x{:02x}", byte)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Token::CString(s) => {
+                write!(f, "c")?;
+                Self::write_string(f, s)
+            }
+            Token::WideString(s) => {
+                write!(f, "u16")?;
+                Self::write_string(f, s)
+            }
+            Token::BinaryString(bytes) => {
+                write!(f, "bin\"")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                write!(f, "\"")
+            }
+            Token::Char(c) => {
+                write!(f, "'")?;
+                Self::write_escaped_char(f, *c, Some('\''))?;
+                write!(f, "'")
+            }
+            Token::Number {
+                value,
+                format,
+                suffix,
+                ..
+            } => {
+                if let Some((prefix, _)) = NUMBER_FORMATS.entries().find(|(_, x)| *x == format) {
+                    write!(f, "0{}", prefix)?;
+                }
+                write!(f, "{}{}", value, suffix.unwrap_or(""))
+            }
+            _ => {
+                let text = KEYWORDS
+                    .entries()
+                    .chain(SYMBOLS.entries())
+                    .find(|(_, x)| *x == self)
+                    .map(|(text, _)| text)
+                    .expect("Missing source text for token");
+                write!(f, "{}", text)
+            }
+        }
+    }
+}
+
+/// Writes a token stream back as source text, which lexes into
+/// the same tokens. Tokens are separated by whitespace, and
+/// comments are followed by a newline to terminate them.
+pub fn print_tokens<'a, 'src: 'a>(tokens: impl IntoIterator<Item = &'a Token<'src>>) -> String {
+    let mut source = String::new();
+    for token in tokens {
+        match token {
+            Token::EOF => break,
+            // Comments that would read as a shebang, or as a line made
+            // only of hashes, can't be the first line of the source
+            Token::Comment(comment)
+                if source.is_empty()
+                    && (comment.starts_with(['\n', '!']) || comment.starts_with("\r\n")) =>
+            {
+                source.push_str(&format!("\n{}\n", token))
+            }
+            Token::Comment(_) | Token::Shebang(_) => source.push_str(&format!("{}\n", token)),
+            _ => source.push_str(&format!("{} ", token)),
+        }
+    }
+
+    source
+}
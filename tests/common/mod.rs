@@ -0,0 +1,8 @@
+use the_1337_lang::*;
+
+// Source map holding `content` as its only file
+pub fn source_map(content: &str) -> (SourceMap, FileId) {
+    let mut sources = SourceMap::new();
+    let file = sources.add_virtual("test.1337", content);
+    (sources, file)
+}
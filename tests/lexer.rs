@@ -0,0 +1,497 @@
+use std::borrow::Cow;
+
+use the_1337_lang::*;
+
+mod common;
+use common::source_map;
+
+#[test]
+fn lex_multi_megabyte_input() {
+    // 12 tokens per line
+    let line = "value := compute(12, \"text\") + 0x1f_u8; # comment\n";
+    let line_count = 4 * 1024 * 1024 / line.len();
+    let content = line.repeat(line_count);
+
+    let (sources, file) = source_map(&content);
+    let tokens = Lexer::new(&sources, file).tokenize_all();
+    assert_eq!(tokens.len(), line_count * 12 + 1);
+    assert_eq!(tokens.last().map(|x| &x.token), Some(&Token::EOF));
+}
+
+#[test]
+fn warn_once_per_identifier_spelling() {
+    // The second identifier starts with a Cyrillic 'ѕ'
+    let (sources, file) = source_map("scope ѕcope ѕcope ѕcope");
+    let mut lexer = Lexer::new(&sources, file);
+    lexer.tokenize_all();
+
+    let messages = lexer
+        .warnings()
+        .iter()
+        .map(|x| x.message.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        messages,
+        [
+            "identifier 'ѕcope' mixes multiple scripts",
+            "identifier 'ѕcope' is confusable with 'scope'",
+        ]
+    );
+}
+
+#[test]
+fn max_symbol_len_matches_symbols() {
+    let max_len = SYMBOLS.keys().map(|x| x.chars().count()).max();
+    assert_eq!(max_len, Some(MAX_SYMBOL_LEN));
+}
+
+#[test]
+fn trivia_reproduces_source() {
+    let sources = [
+        "",
+        "  \n\t # only trivia\n",
+        "#!/usr/bin/env 1337c run\r\nmain := () {\r\n\tputs(c\"hi\"); # greet\r\n}\r\n",
+        "a := 1; ##\nblock\n## b := 2; ### trailing\ncomment ###\n",
+        "x := \"\"\"\n\tlong \"\" string\n\t\"\"\";\n  \n# trailing comment at EOF",
+        "\u{feff} bad ` chars \u{0}\r\r\n",
+    ];
+    for source in sources {
+        let (sources, file) = source_map(source);
+        let tokens = Lexer::with_trivia(&sources, file).tokenize_all();
+        assert_eq!(source_of_tokens(&tokens, source), source);
+    }
+}
+
+// Small deterministic generator, so that failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % n
+    }
+}
+
+#[test]
+fn printed_tokens_lex_into_the_same_tokens() {
+    let pieces = [
+        " ", "\n", "\r", "\t", "#", "##", "#!", "!", "\"", "\"\"\"", "'", "\\", "\\n", "a", "é",
+        "_x", "b", "c", "u16", "bin", "0", "1.5", "0x", "e", "+", "-", "<", "<=", "=", ":", ";",
+        "(", ")", "{", "}", "[", "]", ".", "fn", "return",
+    ];
+    let mut rng = Rng(1337);
+    for _ in 0..20000 {
+        let source = (0..rng.below(12))
+            .map(|_| pieces[rng.below(pieces.len())])
+            .collect::<String>();
+        let (sources, file) = source_map(&source);
+        let tokens = Lexer::new(&sources, file)
+            .map(|x| x.token)
+            .collect::<Vec<Token>>();
+        // Invalid tokens have no source text to print
+        if tokens.contains(&Token::Invalid) {
+            continue;
+        }
+
+        let printed = print_tokens(&tokens);
+        let (sources, file) = source_map(&printed);
+        let relexed = Lexer::new(&sources, file)
+            .map(|x| x.token)
+            .collect::<Vec<Token>>();
+        assert_eq!(relexed, tokens, "{:?} printed as {:?}", source, printed);
+    }
+}
+
+fn errors(content: &str) -> Vec<(LexErrorKind, usize, usize)> {
+    let (sources, file) = source_map(content);
+    let mut lexer = Lexer::new(&sources, file);
+    lexer.tokenize_all();
+    lexer
+        .errors()
+        .iter()
+        .map(|x| (x.kind.clone(), x.start_pos.index, x.end_pos.index))
+        .collect()
+}
+
+#[test]
+fn prefixed_string_errors_point_at_the_offending_bytes() {
+    assert_eq!(
+        errors(r#"x c"ab\0c""#),
+        [(LexErrorKind::NulInTerminatedString, 6, 8)]
+    );
+    assert_eq!(
+        errors("u16\"a\0\""),
+        [(LexErrorKind::NulInTerminatedString, 5, 6)]
+    );
+    assert_eq!(
+        errors(r#"bin"7f 4g""#),
+        [(LexErrorKind::InvalidBinaryDigit('g'), 8, 9)]
+    );
+    assert_eq!(
+        errors(r#"bin"7f 4""#),
+        [(LexErrorKind::IncompleteByte, 7, 8)]
+    );
+    assert_eq!(
+        errors(r#"bin"""7f "" 45""""#),
+        [(LexErrorKind::InvalidBinaryDigit('"'), 9, 11)]
+    );
+    // Errors about the whole string start at its prefix
+    assert_eq!(
+        errors(r#"x c"abc"#),
+        [(LexErrorKind::UnterminatedString, 2, 7)]
+    );
+}
+
+#[test]
+fn binary_strings_decode_hex_digits() {
+    let (sources, file) = source_map("bin\"7f 45\n4C 46\"");
+    let tokens = Lexer::new(&sources, file)
+        .map(|x| x.token)
+        .collect::<Vec<Token>>();
+    assert_eq!(
+        tokens,
+        [
+            Token::BinaryString(vec![0x7f, 0x45, 0x4c, 0x46]),
+            Token::EOF
+        ]
+    );
+}
+
+// Text of a token that is borrowed from its source
+fn borrowed_texts<'a>(token: &'a TokenInfo) -> Vec<&'a [u8]> {
+    let mut texts = match &token.token {
+        Token::Comment(text)
+        | Token::Shebang(text)
+        | Token::Identifier(Cow::Borrowed(text))
+        | Token::String(Cow::Borrowed(text))
+        | Token::CString(Cow::Borrowed(text))
+        | Token::WideString(Cow::Borrowed(text)) => vec![text.as_bytes()],
+        Token::ByteString(Cow::Borrowed(bytes)) => vec![*bytes],
+        Token::Number { value, suffix, .. } => {
+            let value = match value {
+                Cow::Borrowed(value) => Some(value.as_bytes()),
+                Cow::Owned(_) => None,
+            };
+            value.into_iter().chain(suffix.map(str::as_bytes)).collect()
+        }
+        _ => vec![],
+    };
+    texts.extend(
+        token
+            .leading_trivia
+            .iter()
+            .chain(&token.trailing_trivia)
+            .map(|x| x.text.as_bytes()),
+    );
+    texts
+}
+
+fn lexer(sources: &SourceMap, file: FileId, trivia: bool) -> Lexer<'_> {
+    match trivia {
+        true => Lexer::with_trivia(sources, file),
+        false => Lexer::new(sources, file),
+    }
+}
+
+#[test]
+fn relex_matches_full_lex() {
+    let sources = [
+        "#!/bin/1337c\nmain := () {\n\tputs(c\"hi\", b\"x\", u16\"y\"); # greet\n\tx := 0x1f_u8 + 1.5e3f32;\n}\n",
+        "a := \"\"\"\nlong\n\"\"\"; ##\nblock\n## b := 'c';\r\n",
+        "##\ny z ##\n#!x\n",
+    ];
+    let pieces = [
+        "", " ", "\n", "#", "##", "\"", "\"\"\"", "a", "é", "1", "0x", ".5", "u8", "}", "'",
+    ];
+    let mut rng = Rng(1337);
+    for trivia in [false, true] {
+        for source in sources {
+            let (old_sources, old_file) = source_map(source);
+            let tokens = lexer(&old_sources, old_file, trivia).tokenize_all();
+            for _ in 0..1000 {
+                let mut start = rng.below(source.len() + 1);
+                while !source.is_char_boundary(start) {
+                    start -= 1;
+                }
+                let mut end = (start + rng.below(6)).min(source.len());
+                while !source.is_char_boundary(end) {
+                    end -= 1;
+                }
+                let edit = TextEdit {
+                    range: start..end,
+                    replacement: pieces[rng.below(pieces.len())].to_owned(),
+                };
+                let mut new_source = source.to_owned();
+                new_source.replace_range(edit.range.clone(), &edit.replacement);
+
+                let (new_sources, new_file) = source_map(&new_source);
+                let full = lexer(&new_sources, new_file, trivia).tokenize_all();
+                let relexed = lexer(&new_sources, new_file, trivia).relex(
+                    old_sources.content(old_file),
+                    tokens.clone(),
+                    &edit,
+                );
+                assert_eq!(
+                    format!("{:?}", relexed),
+                    format!("{:?}", full),
+                    "{:?} after {:?}",
+                    new_source,
+                    edit,
+                );
+
+                let source_range = new_sources.content(new_file).as_bytes().as_ptr_range();
+                for text in relexed.iter().flat_map(borrowed_texts) {
+                    let text_range = text.as_ptr_range();
+                    assert!(
+                        source_range.start <= text_range.start
+                            && text_range.end <= source_range.end,
+                        "{:?} doesn't borrow from {:?}",
+                        text,
+                        new_source,
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Value, format, whether it is a float and suffix of a lone number literal
+fn number(content: &str) -> (String, NumberFormat, bool, Option<String>) {
+    let (sources, file) = source_map(content);
+    let mut lexer = Lexer::new(&sources, file);
+    let tokens = lexer.tokenize_all();
+    assert!(lexer.errors().is_empty(), "{:?}", lexer.errors());
+    match &tokens[..] {
+        [
+            TokenInfo {
+                token:
+                    Token::Number {
+                        value,
+                        format,
+                        is_float,
+                        suffix,
+                    },
+                ..
+            },
+            _,
+        ] => (
+            value.to_string(),
+            format.clone(),
+            *is_float,
+            suffix.map(str::to_owned),
+        ),
+        _ => panic!("{:?} isn't a single number: {:?}", content, tokens),
+    }
+}
+
+#[test]
+fn valid_number_literals() {
+    use NumberFormat::*;
+
+    let suffix = |x: &str| Some(x.to_owned());
+    assert_eq!(
+        number("1_000_000"),
+        ("1000000".into(), Decimal, false, None)
+    );
+    assert_eq!(number("1e-9"), ("1e-9".into(), Decimal, true, None));
+    assert_eq!(number("0x1p4"), ("1p4".into(), Hex, true, None));
+    assert_eq!(number("0o17"), ("17".into(), Octal, false, None));
+    assert_eq!(number("0b1010"), ("1010".into(), Binary, false, None));
+    assert_eq!(
+        number("255u8"),
+        ("255".into(), Decimal, false, suffix("u8"))
+    );
+    assert_eq!(
+        number("1.5f32"),
+        ("1.5".into(), Decimal, true, suffix("f32"))
+    );
+    assert_eq!(number("1f64"), ("1".into(), Decimal, true, suffix("f64")));
+    // The exponent marker is always lowercase
+    assert_eq!(number("1E5"), ("1e5".into(), Decimal, true, None));
+}
+
+#[test]
+fn invalid_number_literals_point_at_the_offending_part() {
+    use LexErrorKind::*;
+
+    assert_eq!(errors("0x"), [(MissingDigits, 0, 2)]);
+    assert_eq!(errors("1.2.3"), [(UnexpectedDecimalPoint, 3, 4)]);
+    assert_eq!(errors("0b102"), [(InvalidDigit('2'), 4, 5)]);
+    assert_eq!(errors("0o8"), [(InvalidDigit('8'), 2, 3)]);
+    assert_eq!(errors("0x1.8"), [(MissingExponent, 3, 5)]);
+    assert_eq!(errors("1e"), [(MissingDigits, 1, 2)]);
+    assert_eq!(errors("1.5e+"), [(MissingDigits, 3, 5)]);
+    assert_eq!(errors("0b1.0"), [(UnexpectedDecimalPoint, 3, 4)]);
+    assert_eq!(errors("1u"), [(InvalidSuffix("u".into()), 1, 2)]);
+    // Integer suffixes don't apply to floats, nor float suffixes to hex
+    assert_eq!(errors("1e5i32"), [(InvalidSuffix("i32".into()), 3, 6)]);
+    assert_eq!(errors("0x1f32"), []);
+    assert_eq!(errors("0x1p1f32"), [(InvalidSuffix("f32".into()), 5, 8)]);
+    // Spans are relative to the start of the literal
+    assert_eq!(errors("x := 0x;"), [(MissingDigits, 5, 7)]);
+}
+
+#[test]
+fn number_followed_by_member_access() {
+    let (sources, file) = source_map("1.foo");
+    let tokens = Lexer::new(&sources, file)
+        .map(|x| x.token)
+        .collect::<Vec<Token>>();
+    assert_eq!(
+        tokens,
+        [
+            Token::Number {
+                value: "1".into(),
+                format: NumberFormat::Decimal,
+                is_float: false,
+                suffix: None,
+            },
+            Token::Dot,
+            Token::Identifier("foo".into()),
+            Token::EOF,
+        ]
+    );
+}
+
+// Content of a lone string literal
+fn string(content: &str) -> String {
+    let (sources, file) = source_map(content);
+    let mut lexer = Lexer::new(&sources, file);
+    let tokens = lexer.tokenize_all();
+    assert!(lexer.errors().is_empty(), "{:?}", lexer.errors());
+    match &tokens[..] {
+        [
+            TokenInfo {
+                token: Token::String(value),
+                ..
+            },
+            _,
+        ] => value.to_string(),
+        _ => panic!("{:?} isn't a single string: {:?}", content, tokens),
+    }
+}
+
+#[test]
+fn long_strings_are_dedented_by_their_closing_line() {
+    // A blank closing line isn't part of the string
+    assert_eq!(string("\"\"\"\n    a\n      b\n    \"\"\""), "a\n  b");
+    // Text before the closing quotes is
+    assert_eq!(string("\"\"\"\n  a\n  b\"\"\""), "a\nb");
+    assert_eq!(string("\"\"\"\n    a\n  \"\"\""), "  a");
+}
+
+#[test]
+fn long_strings_keep_blank_lines() {
+    // Blank lines don't need the full indentation
+    assert_eq!(string("\"\"\"\n    a\n\n  \n    b\n    \"\"\""), "a\n\n\nb");
+    assert_eq!(string("\"\"\"\n  a\n     \n  \"\"\""), "a\n   ");
+}
+
+#[test]
+fn long_strings_with_crlf_line_breaks() {
+    assert_eq!(
+        string("\"\"\"\r\n    a\r\n      b\r\n    \"\"\""),
+        "a\r\n  b"
+    );
+}
+
+#[test]
+fn long_strings_closed_on_their_opening_line() {
+    assert_eq!(string("\"\"\"a \"\" b\"\"\""), "a \"\" b");
+    assert_eq!(string("\"\"\"  a\"\"\""), "  a");
+}
+
+#[test]
+fn long_string_lines_must_be_indented_like_the_closing_line() {
+    let content = "x := \"\"\"\n    a\n  b\n    \"\"\";";
+    assert_eq!(
+        errors(content),
+        [(LexErrorKind::InsufficientIndentation, 15, 17)]
+    );
+
+    let (sources, file) = source_map(content);
+    let tokens = Lexer::new(&sources, file)
+        .map(|x| x.token)
+        .collect::<Vec<Token>>();
+    assert_eq!(
+        tokens,
+        [
+            Token::Identifier("x".into()),
+            Token::Walrus,
+            Token::Invalid,
+            Token::SemiColon,
+            Token::EOF,
+        ]
+    );
+}
+
+#[test]
+fn trivia_mode_yields_the_same_tokens() {
+    let contents = [
+        "a ## b",
+        "a ## b ##\nc # d\n",
+        "## banner\nx := 1; ### unterminated ##",
+    ];
+    for content in contents {
+        let (sources, file) = source_map(content);
+        let mut lexers = [false, true].map(|trivia| lexer(&sources, file, trivia));
+        let [tokens, trivia_tokens] = lexers.each_mut().map(|lexer| {
+            lexer
+                .filter(|x| !matches!(x.token, Token::Comment(_)))
+                .map(|x| x.token)
+                .collect::<Vec<Token>>()
+        });
+        assert_eq!(trivia_tokens, tokens, "{:?}", content);
+        assert_eq!(
+            format!("{:?}", lexers[1].errors()),
+            format!("{:?}", lexers[0].errors()),
+            "{:?}",
+            content
+        );
+
+        let tokens = Lexer::with_trivia(&sources, file).tokenize_all();
+        assert_eq!(source_of_tokens(&tokens, content), content);
+    }
+}
+
+#[test]
+fn relex_edits_at_the_start_of_the_file() {
+    // Banner comments and shebangs depend on starting the file
+    let edits = [
+        ("##\ny z", 0..0, "x"),
+        ("x##\ny z", 0..1, ""),
+        ("##\ny z", 0..0, " "),
+        ("x#!y\nz", 0..1, ""),
+        ("#!y\nz", 0..0, "x"),
+    ];
+    for (source, range, replacement) in edits {
+        let edit = TextEdit {
+            range,
+            replacement: replacement.to_owned(),
+        };
+        let mut new_source = source.to_owned();
+        new_source.replace_range(edit.range.clone(), &edit.replacement);
+
+        let (old_sources, old_file) = source_map(source);
+        let (new_sources, new_file) = source_map(&new_source);
+        for trivia in [false, true] {
+            let tokens = lexer(&old_sources, old_file, trivia).tokenize_all();
+            let full = lexer(&new_sources, new_file, trivia).tokenize_all();
+            let relexed = lexer(&new_sources, new_file, trivia).relex(
+                old_sources.content(old_file),
+                tokens,
+                &edit,
+            );
+            assert_eq!(
+                format!("{:?}", relexed),
+                format!("{:?}", full),
+                "{:?} after {:?}",
+                new_source,
+                edit,
+            );
+        }
+    }
+}
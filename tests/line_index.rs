@@ -0,0 +1,109 @@
+use the_1337_lang::*;
+
+const UNITS: [ColumnUnit; 3] = [
+    ColumnUnit::Utf8,
+    ColumnUnit::Utf16,
+    ColumnUnit::Display { tab_width: 4 },
+];
+
+fn line_col(line: usize, column: usize) -> LineCol {
+    LineCol { line, column }
+}
+
+#[test]
+fn offsets_round_trip_through_line_and_column() {
+    let text = "aé😀x\n\tb\t\r\n  ü\u{10000}\n";
+    let index = LineIndex::new(text);
+    for unit in UNITS {
+        for (offset, _) in text.char_indices() {
+            let position = index.line_col(offset, unit);
+            assert_eq!(index.offset(position, unit), Some(offset), "{:?}", unit);
+        }
+    }
+}
+
+#[test]
+fn columns_count_in_each_unit() {
+    let index = LineIndex::new("aé😀x");
+    let columns = UNITS.map(|unit| index.line_col(7, unit).column);
+    assert_eq!(columns, [7, 4, 4]);
+
+    // Offsets inside a character snap to its start
+    assert_eq!(index.line_col(2, ColumnUnit::Utf8), line_col(0, 1));
+    assert_eq!(index.line_col(5, ColumnUnit::Utf16), line_col(0, 2));
+    assert_eq!(index.offset(line_col(0, 3), ColumnUnit::Utf16), Some(3));
+    assert_eq!(index.offset(line_col(0, 5), ColumnUnit::Utf8), Some(3));
+}
+
+#[test]
+fn combining_marks_take_no_display_column() {
+    // `e` followed by a combining acute accent
+    let index = LineIndex::new("e\u{301}x");
+    let display = ColumnUnit::Display { tab_width: 4 };
+    assert_eq!(index.line_col(1, display), line_col(0, 1));
+    assert_eq!(index.line_col(3, display), line_col(0, 1));
+    assert_eq!(index.offset(line_col(0, 1), display), Some(3));
+
+    assert_eq!(index.line_col(3, ColumnUnit::Utf16), line_col(0, 2));
+    assert_eq!(index.offset(line_col(0, 1), ColumnUnit::Utf16), Some(1));
+}
+
+#[test]
+fn tabs_advance_to_the_next_tab_stop() {
+    let index = LineIndex::new("\tx\n a\tb");
+    let column = |offset, tab_width| {
+        index
+            .line_col(offset, ColumnUnit::Display { tab_width })
+            .column
+    };
+    assert_eq!(column(1, 4), 4);
+    assert_eq!(column(6, 4), 4);
+    assert_eq!(column(6, 8), 8);
+    assert_eq!(column(6, 1), 3);
+    // Tab stops of zero cells are treated as one cell
+    assert_eq!(column(6, 0), 3);
+
+    // Columns inside a tab resolve to the tab
+    let display = ColumnUnit::Display { tab_width: 4 };
+    assert_eq!(index.offset(line_col(1, 3), display), Some(5));
+    assert_eq!(index.offset(line_col(1, 4), display), Some(6));
+}
+
+#[test]
+fn crlf_line_breaks_end_lines_at_the_newline() {
+    let index = LineIndex::new("ab\r\ncd");
+    assert_eq!(index.line_count(), 2);
+    assert_eq!(index.line_col(2, ColumnUnit::Utf8), line_col(0, 2));
+    assert_eq!(index.line_col(4, ColumnUnit::Utf8), line_col(1, 0));
+    // The `\r` is part of the line, the `\n` isn't
+    assert_eq!(index.offset(line_col(0, 3), ColumnUnit::Utf8), Some(3));
+    assert_eq!(index.offset(line_col(0, 4), ColumnUnit::Utf8), None);
+    assert_eq!(index.offset(line_col(1, 2), ColumnUnit::Utf8), Some(6));
+}
+
+#[test]
+fn empty_source_has_one_empty_line() {
+    let index = LineIndex::new("");
+    assert_eq!(index.line_count(), 1);
+    for unit in UNITS {
+        assert_eq!(index.line_col(0, unit), line_col(0, 0));
+        assert_eq!(index.offset(line_col(0, 0), unit), Some(0));
+        assert_eq!(index.offset(line_col(0, 1), unit), None);
+        assert_eq!(index.offset(line_col(1, 0), unit), None);
+    }
+}
+
+#[test]
+fn positions_past_the_end() {
+    let index = LineIndex::new("ab\ncé");
+    for unit in UNITS {
+        // Offsets clamp to the end of the source
+        assert_eq!(index.line_col(100, unit), index.line_col(6, unit));
+        assert_eq!(index.offset(line_col(2, 0), unit), None);
+        assert_eq!(index.offset(line_col(0, 3), unit), None);
+        assert_eq!(index.offset(line_col(0, 2), unit), Some(2));
+    }
+    assert_eq!(index.offset(line_col(1, 3), ColumnUnit::Utf8), Some(6));
+    assert_eq!(index.offset(line_col(1, 2), ColumnUnit::Utf16), Some(6));
+    assert_eq!(index.offset(line_col(1, 3), ColumnUnit::Utf16), None);
+}
@@ -0,0 +1,241 @@
+use the_1337_lang::*;
+
+mod common;
+use common::source_map;
+
+fn parse(content: &str) -> Vec<Node> {
+    let (sources, file) = source_map(content);
+    let mut parser = Parser::new(Lexer::new(&sources, file)).unwrap();
+    std::iter::from_fn(|| parser.parse())
+        .map(|x| x.node)
+        .collect()
+}
+
+#[test]
+fn parser_appends_missing_eof() {
+    let (sources, file) = source_map("f();");
+    let mut tokens = Lexer::new(&sources, file).tokenize_all();
+    tokens.pop();
+
+    let mut parser = Parser::new(tokens).unwrap();
+    assert!(matches!(
+        parser.parse().map(|x| x.node),
+        Some(Node::Expression(Expression::FunctionCall { .. }))
+    ));
+    assert_eq!(parser.parse(), None);
+}
+
+#[test]
+fn parser_rejects_empty_stream() {
+    assert!(Parser::new(vec![]).is_err());
+}
+
+#[test]
+fn lambda_ends_the_expression() {
+    let lambda_declaration = |node: &Node| {
+        matches!(
+            node,
+            Node::Expression(Expression::Declaration { value: Some(value), .. })
+                if matches!(**value, Expression::AnonymousFunctionDefinition { .. })
+        )
+    };
+
+    // `-x;` isn't a valid statement, but it isn't part of the lambda either
+    let nodes = parse("f := () i32 { return 1; }\n-x;");
+    assert!(lambda_declaration(&nodes[0]), "{:?}", nodes);
+
+    let nodes = parse("main := () {}\n*ptr = 10;");
+    assert_eq!(nodes.len(), 2, "{:?}", nodes);
+    assert!(lambda_declaration(&nodes[0]), "{:?}", nodes);
+    assert!(matches!(
+        nodes[1],
+        Node::Statement(Statement::Assignment {
+            target: Expression::Unary {
+                op: UnaryOperator::Dereference,
+                ..
+            },
+            ..
+        })
+    ));
+
+    // Neither indexing nor binary operators continue a lambda
+    for source in ["g := () {}\n[0];", "g := () {}\n* 2;"] {
+        let nodes = parse(source);
+        assert!(lambda_declaration(&nodes[0]), "{:?}", nodes);
+    }
+}
+
+// Expression as an S-expression, with explicit grouping
+fn sexp(expression: &Expression) -> String {
+    match expression {
+        Expression::Identifier(name) => name.clone(),
+        Expression::Number { value, .. } => value.clone(),
+        Expression::Binary { op, lhs, rhs } => {
+            format!("({:?} {} {})", op, sexp(lhs), sexp(rhs))
+        }
+        Expression::Unary { op, operand } => format!("({:?} {})", op, sexp(operand)),
+        Expression::Paren(inner) => format!("(Paren {})", sexp(inner)),
+        Expression::Field { base, field } => format!("(Field {} {})", sexp(base), field),
+        Expression::Index { base, index } => format!("(Index {} {})", sexp(base), sexp(index)),
+        Expression::FunctionCall {
+            identifier,
+            arguments,
+        } => {
+            let arguments = arguments.iter().map(sexp).collect::<Vec<String>>();
+            format!("(Call {} [{}])", identifier, arguments.join(" "))
+        }
+        _ => format!("{:?}", expression),
+    }
+}
+
+// Value of `x := <source>;`, as an S-expression
+fn parse_value(source: &str) -> String {
+    let nodes = parse(&format!("x := {};", source));
+    match &nodes[..] {
+        [
+            Node::Expression(Expression::Declaration {
+                value: Some(value), ..
+            }),
+        ] => sexp(value),
+        _ => panic!("{:?} isn't a single declaration: {:?}", source, nodes),
+    }
+}
+
+#[test]
+fn binary_operators_are_left_associative() {
+    assert_eq!(parse_value("1 - 2 - 3"), "(Subtract (Subtract 1 2) 3)");
+    assert_eq!(parse_value("a / b * c"), "(Multiply (Divide a b) c)");
+    assert_eq!(parse_value("a << 1 >> 2"), "(ShiftRight (ShiftLeft a 1) 2)");
+}
+
+#[test]
+fn binary_operators_bind_by_precedence() {
+    assert_eq!(parse_value("1 + 2 * 3"), "(Add 1 (Multiply 2 3))");
+    assert_eq!(
+        parse_value("a || b && c == d + e * f"),
+        "(LogicalOr a (LogicalAnd b (Equals c (Add d (Multiply e f)))))"
+    );
+    assert_eq!(
+        parse_value("a | b ^ c & d"),
+        "(BitwiseOr a (BitwiseXor b (BitwiseAnd c d)))"
+    );
+    assert_eq!(
+        parse_value("a < b == c >= d"),
+        "(Equals (LessThan a b) (GreaterThanOrEquals c d))"
+    );
+    assert_eq!(
+        parse_value("a + b << c % d"),
+        "(ShiftLeft (Add a b) (Modulo c d))"
+    );
+}
+
+#[test]
+fn unary_operators_bind_tighter_than_binary_ones() {
+    assert_eq!(parse_value("-a * b"), "(Multiply (Negate a) b)");
+    assert_eq!(parse_value("a - -b"), "(Subtract a (Negate b))");
+    assert_eq!(parse_value("!~-a"), "(LogicalNot (BitwiseNot (Negate a)))");
+    assert_eq!(parse_value("**p"), "(Dereference (Dereference p))");
+    assert_eq!(parse_value("&a & b"), "(BitwiseAnd (AddressOf a) b)");
+}
+
+#[test]
+fn parentheses_group_expressions() {
+    assert_eq!(parse_value("(a + b) * 2"), "(Multiply (Paren (Add a b)) 2)");
+    assert_eq!(
+        parse_value("a - (b - c)"),
+        "(Subtract a (Paren (Subtract b c)))"
+    );
+    assert_eq!(parse_value("((a))"), "(Paren (Paren a))");
+}
+
+#[test]
+fn postfix_operators_bind_tightest() {
+    assert_eq!(
+        parse_value("-a.b[i + 1].c"),
+        "(Negate (Field (Index (Field a b) (Add i 1)) c))"
+    );
+    assert_eq!(parse_value("*p.x"), "(Dereference (Field p x))");
+    assert_eq!(parse_value("(*p).x"), "(Field (Paren (Dereference p)) x)");
+    assert_eq!(parse_value("f(a, b)[0]"), "(Index (Call f [a b]) 0)");
+}
+
+// Target, operator and value of a lone assignment
+fn parse_assignment(source: &str) -> (String, Option<BinaryOperator>, String) {
+    let nodes = parse(source);
+    match &nodes[..] {
+        [Node::Statement(Statement::Assignment { target, op, value })] => {
+            (sexp(target), *op, sexp(value))
+        }
+        _ => panic!("{:?} isn't a single assignment: {:?}", source, nodes),
+    }
+}
+
+#[test]
+fn assignments_to_place_expressions() {
+    let assignment = |target: &str, op, value: &str| (target.to_owned(), op, value.to_owned());
+    assert_eq!(parse_assignment("a = 10;"), assignment("a", None, "10"));
+    assert_eq!(
+        parse_assignment("*ptr = a + 1;"),
+        assignment("(Dereference ptr)", None, "(Add a 1)")
+    );
+    assert_eq!(
+        parse_assignment("point.x = 1;"),
+        assignment("(Field point x)", None, "1")
+    );
+    assert_eq!(
+        parse_assignment("array[i] = 1;"),
+        assignment("(Index array i)", None, "1")
+    );
+    assert_eq!(
+        parse_assignment("(*p).x = 1;"),
+        assignment("(Field (Paren (Dereference p)) x)", None, "1")
+    );
+    assert_eq!(
+        parse_assignment("(a) = 1;"),
+        assignment("(Paren a)", None, "1")
+    );
+}
+
+#[test]
+fn compound_assignments_keep_their_operator() {
+    let operators = [
+        ("+=", BinaryOperator::Add),
+        ("-=", BinaryOperator::Subtract),
+        ("*=", BinaryOperator::Multiply),
+        ("/=", BinaryOperator::Divide),
+        ("%=", BinaryOperator::Modulo),
+        ("&=", BinaryOperator::BitwiseAnd),
+        ("|=", BinaryOperator::BitwiseOr),
+        ("^=", BinaryOperator::BitwiseXor),
+        ("<<=", BinaryOperator::ShiftLeft),
+        (">>=", BinaryOperator::ShiftRight),
+    ];
+    for (symbol, op) in operators {
+        let source = format!("array[a] {} b * 2;", symbol);
+        assert_eq!(
+            parse_assignment(&source),
+            (
+                "(Index array a)".to_owned(),
+                Some(op),
+                "(Multiply b 2)".to_owned()
+            ),
+        );
+    }
+}
+
+#[test]
+fn assignments_to_other_expressions_are_errors() {
+    for source in ["a + b = 3;", "f() = 1;", "*p + 1 = 2;", "(a + b) = 3;"] {
+        let (sources, file) = source_map(source);
+        let mut parser = Parser::new(Lexer::new(&sources, file)).unwrap();
+        let node = parser.parse().unwrap();
+        assert_eq!(node.node, Node::Invalid, "{:?}", source);
+        assert!(
+            node.message.contains("cannot assign to expression"),
+            "{:?}: {}",
+            source,
+            node.message
+        );
+        assert_eq!(parser.parse(), None, "{:?}", source);
+    }
+}
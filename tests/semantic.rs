@@ -0,0 +1,132 @@
+use the_1337_lang::*;
+
+mod common;
+use common::source_map;
+
+fn analyze(content: &str) -> Result<Program, String> {
+    let (sources, file) = source_map(content);
+    let mut parser = Parser::new(Lexer::new(&sources, file)).expect("lexer output ends in EOF");
+    let mut nodes = vec![];
+    while let Some(node) = parser.parse() {
+        assert_eq!(node.message, "", "failed to parse {:?}", content);
+        nodes.push(node);
+    }
+
+    SemanticAnalyzer::new(&nodes).analyze()
+}
+
+fn symbol_type(program: &Program, name: &str) -> SemanticType {
+    program.scopes[0][name].sem_type.clone()
+}
+
+#[test]
+fn string_literals_have_distinct_types() {
+    let program = analyze(
+        r#"
+        text := "hi";
+        bytes := b"hi";
+        blob := bin"6869";
+        c_string := c"hi";
+        wide : *u16 = u16"hi";
+        "#,
+    )
+    .unwrap();
+
+    let array = |element, length| SemanticType::Array {
+        element: Box::new(element),
+        length,
+    };
+    assert_eq!(symbol_type(&program, "text"), array(SemanticType::Int8, 2));
+    assert_eq!(
+        symbol_type(&program, "bytes"),
+        array(SemanticType::UInt8, 2)
+    );
+    assert_eq!(symbol_type(&program, "blob"), array(SemanticType::UInt8, 2));
+    assert_eq!(
+        symbol_type(&program, "c_string"),
+        SemanticType::Pointer(Box::new(SemanticType::Int8))
+    );
+    assert_eq!(
+        symbol_type(&program, "wide"),
+        SemanticType::Pointer(Box::new(SemanticType::UInt16))
+    );
+}
+
+#[test]
+fn declared_type_must_match_literal() {
+    assert!(analyze(r#"wide : *i8 = u16"hi";"#).is_err());
+}
+
+#[test]
+fn unknown_return_type_is_an_error() {
+    let error = analyze("fn f() word { return 1; }").unwrap_err();
+    assert!(error.contains("does not exist"), "{}", error);
+}
+
+#[test]
+fn lambda_returns_are_checked_against_their_own_type() {
+    let program = analyze("f := () i32 { return 1; }").unwrap();
+    assert!(matches!(
+        symbol_type(&program, "f"),
+        SemanticType::Function {
+            return_type: Some(_),
+            ..
+        }
+    ));
+
+    let error = analyze("f := () { return 1; }").unwrap_err();
+    assert!(error.contains("'f' must not return a value"), "{}", error);
+
+    let error = analyze("f := () i32 { return \"one\"; }").unwrap_err();
+    assert!(error.contains("'f' returns"), "{}", error);
+
+    // A nested lambda doesn't return from the enclosing function
+    analyze("fn f() { g := () i32 { return 1; } return; }").unwrap();
+    let error = analyze("fn f() i32 { g := () { return 1; } return 1; }").unwrap_err();
+    assert!(error.contains("'g' must not return a value"), "{}", error);
+}
+
+#[test]
+fn number_literals_have_the_type_of_their_suffix() {
+    let program = analyze(
+        r#"
+        c := 10;
+        x := 255u8;
+        big := 1i64;
+        ratio := 1.5;
+        single := 1.5f32;
+        exponent := 1e3;
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(symbol_type(&program, "c"), SemanticType::Int32);
+    assert_eq!(symbol_type(&program, "x"), SemanticType::UInt8);
+    assert_eq!(symbol_type(&program, "big"), SemanticType::Int64);
+    assert_eq!(symbol_type(&program, "ratio"), SemanticType::Float64);
+    assert_eq!(symbol_type(&program, "single"), SemanticType::Float32);
+    assert_eq!(symbol_type(&program, "exponent"), SemanticType::Float64);
+}
+
+#[test]
+fn unsuffixed_numbers_take_the_declared_type() {
+    let program = analyze(
+        r#"
+        b : i32 = 10;
+        byte : u8 = 10;
+        half : f32 = 0.5;
+        whole : f64 = 1;
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(symbol_type(&program, "b"), SemanticType::Int32);
+    assert_eq!(symbol_type(&program, "byte"), SemanticType::UInt8);
+    assert_eq!(symbol_type(&program, "half"), SemanticType::Float32);
+    assert_eq!(symbol_type(&program, "whole"), SemanticType::Float64);
+
+    assert!(analyze("x : i32 = 1.5;").is_err());
+    assert!(analyze("x : u8 = 1u16;").is_err());
+    analyze("fn f() u8 { return 1; }").unwrap();
+    assert!(analyze("fn f() u8 { return 1.5; }").is_err());
+}